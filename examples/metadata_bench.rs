@@ -0,0 +1,38 @@
+//! Compare the full parse of a beatmap with the parse of its metadata only.
+//!
+//! Run with `cargo run --release --example metadata_bench`.
+
+use osu_beatmap_parser::BeatmapLevel;
+use std::fs;
+use std::time::Instant;
+
+const TEST_BEATMAP_LEVEL_PATH: &str = "./assets/examples/test.osu";
+const ITERATIONS: u32 = 200;
+
+fn main() {
+    let s = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+
+    let start = Instant::now();
+    let mut titles = 0;
+    for _ in 0..ITERATIONS {
+        let beatmap_level = BeatmapLevel::parse(&s).unwrap();
+        titles += beatmap_level.metadata.title.len();
+    }
+    println!(
+        "full parse: {:?} ({} bytes of titles)",
+        start.elapsed(),
+        titles
+    );
+
+    let start = Instant::now();
+    let mut titles = 0;
+    for _ in 0..ITERATIONS {
+        let metadata = BeatmapLevel::parse_metadata_only(&s).unwrap();
+        titles += metadata.title.len();
+    }
+    println!(
+        "metadata only: {:?} ({} bytes of titles)",
+        start.elapsed(),
+        titles
+    );
+}
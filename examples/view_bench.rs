@@ -0,0 +1,30 @@
+//! Compare the owned parse of a beatmap with the borrowed view on a large map.
+//!
+//! Run with `cargo run --release --example view_bench`.
+
+use osu_beatmap_parser::BeatmapLevel;
+use std::fs;
+use std::time::Instant;
+
+const TEST_BEATMAP_LEVEL_PATH: &str = "./assets/examples/test.osu";
+const ITERATIONS: u32 = 200;
+
+fn main() {
+    let s = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+
+    let start = Instant::now();
+    let mut objects = 0;
+    for _ in 0..ITERATIONS {
+        let beatmap_level = BeatmapLevel::parse(&s).unwrap();
+        objects += beatmap_level.hit_objects.len();
+    }
+    println!("owned parse: {:?} ({} objects)", start.elapsed(), objects);
+
+    let start = Instant::now();
+    let mut objects = 0;
+    for _ in 0..ITERATIONS {
+        let view = BeatmapLevel::view(&s);
+        objects += view.hit_objects().count();
+    }
+    println!("borrowed view: {:?} ({} objects)", start.elapsed(), objects);
+}
@@ -0,0 +1,164 @@
+use crate::section::difficulty::DifficultySection;
+use crate::section::general::GeneralSection;
+use crate::section::hit_objects::HitObject;
+use crate::section::timing_points::TimingPoint;
+use crate::types::general::Gamemode;
+use crate::BeatmapLevel;
+
+/// Builder creating a beatmap from scratch, starting from the osu! default settings.
+///
+/// ```
+/// use osu_beatmap_parser::builder::BeatmapBuilder;
+/// use osu_beatmap_parser::types::general::Gamemode;
+///
+/// let beatmap = BeatmapBuilder::new()
+///     .audio("song.mp3")
+///     .mode(Gamemode::MANIA)
+///     .title("Title")
+///     .build();
+/// ```
+#[derive(Debug)]
+pub struct BeatmapBuilder {
+    beatmap: BeatmapLevel,
+}
+
+impl Default for BeatmapBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BeatmapBuilder {
+    pub fn new() -> Self {
+        Self {
+            beatmap: BeatmapLevel {
+                general: GeneralSection::osu_default(),
+                difficulty: DifficultySection::osu_default(),
+                ..BeatmapLevel::default()
+            },
+        }
+    }
+
+    /// Location of the audio file relative to the beatmap directory.
+    pub fn audio(mut self, filename: &str) -> Self {
+        self.beatmap.general.audio_filename = filename.to_string();
+        self
+    }
+
+    pub fn mode(mut self, mode: Gamemode) -> Self {
+        self.beatmap.general.mode = mode;
+        self
+    }
+
+    /// Song title, used for both the romanised and the unicode title.
+    pub fn title(mut self, title: &str) -> Self {
+        self.beatmap.metadata.title = title.to_string();
+        self.beatmap.metadata.title_unicode = title.to_string();
+        self
+    }
+
+    /// Song artist, used for both the romanised and the unicode artist.
+    pub fn artist(mut self, artist: &str) -> Self {
+        self.beatmap.metadata.artist = artist.to_string();
+        self.beatmap.metadata.artist_unicode = artist.to_string();
+        self
+    }
+
+    pub fn creator(mut self, creator: &str) -> Self {
+        self.beatmap.metadata.creator = creator.to_string();
+        self
+    }
+
+    /// Difficulty name.
+    pub fn version(mut self, version: &str) -> Self {
+        self.beatmap.metadata.version = version.to_string();
+        self
+    }
+
+    pub fn difficulty(mut self, difficulty: DifficultySection) -> Self {
+        self.beatmap.difficulty = difficulty;
+        self
+    }
+
+    pub fn timing_point(mut self, timing_point: TimingPoint) -> Self {
+        self.beatmap.timing_points.push(timing_point);
+        self
+    }
+
+    pub fn hit_object(mut self, hit_object: HitObject) -> Self {
+        self.beatmap.hit_objects.push(hit_object);
+        self
+    }
+
+    /// Build the beatmap, sorting the timing points and hit objects by time.
+    ///
+    /// A beatmap needs an uninherited timing point to be playable, so if none was given,
+    /// one at 120 BPM is added at the start of the audio.
+    pub fn build(mut self) -> BeatmapLevel {
+        if !self
+            .beatmap
+            .timing_points
+            .iter()
+            .any(|x| bool::from(x.is_uninherited))
+        {
+            self.beatmap.timing_points.insert(
+                0,
+                TimingPoint {
+                    time: 0,
+                    beat_length: 500.0.into(),
+                    meter: 4,
+                    volume: 100,
+                    is_uninherited: true.into(),
+                    ..TimingPoint::default()
+                },
+            );
+        }
+
+        self.beatmap.timing_points.sort_by_key(|x| x.time);
+        self.beatmap.hit_objects.sort_by_key(|x| x.time);
+
+        self.beatmap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::BeatmapBuilder;
+    use crate::section::hit_objects::HitObject;
+    use crate::types::general::Gamemode;
+    use crate::BeatmapLevel;
+
+    #[test]
+    fn build_minimal_beatmap() {
+        let beatmap_level = BeatmapBuilder::new()
+            .audio("song.mp3")
+            .mode(Gamemode::MANIA)
+            .title("X")
+            .version("Easy")
+            .hit_object(HitObject {
+                x: 64,
+                y: 192,
+                time: 1500,
+                ..HitObject::default()
+            })
+            .hit_object(HitObject {
+                x: 192,
+                y: 192,
+                time: 1000,
+                ..HitObject::default()
+            })
+            .build();
+
+        assert_eq!(beatmap_level.timing_points.len(), 1);
+        assert_eq!(beatmap_level.hit_objects[0].time, 1000);
+
+        let reparsed = BeatmapLevel::parse(&beatmap_level.to_string()).unwrap();
+
+        assert_eq!(reparsed.general.audio_filename, "song.mp3");
+        assert_eq!(reparsed.general.mode, Gamemode::MANIA);
+        assert_eq!(reparsed.general.preview_time, -1);
+        assert_eq!(reparsed.metadata.title, "X");
+        assert_eq!(reparsed.difficulty.slider_multiplier, 1.4);
+        assert!(reparsed.diff(&beatmap_level).is_empty());
+    }
+}
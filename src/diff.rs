@@ -0,0 +1,138 @@
+use crate::section::{CommaListElement, CommaListOf};
+use crate::BeatmapLevel;
+
+/// Change of an element of a comma-separated list section, identified by its index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementDiff {
+    /// The element only exists in the other beatmap.
+    Added(usize),
+    /// The element only exists in the original beatmap.
+    Removed(usize),
+    /// The element exists in both beatmaps but differs.
+    Changed(usize),
+}
+
+/// Section differing between two beatmaps.
+/// Comma-separated list sections detail which of their elements changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SectionDiff {
+    General,
+    Editor,
+    Metadata,
+    Difficulty,
+    Events(Vec<ElementDiff>),
+    TimingPoints(Vec<ElementDiff>),
+    Colours,
+    HitObjects(Vec<ElementDiff>),
+    /// Sections not handled by the library.
+    UnknownSections,
+}
+
+/// Compare two comma-separated lists element by element.
+fn diff_list<T>(list: &CommaListOf<T>, other: &CommaListOf<T>) -> Vec<ElementDiff>
+where
+    T: CommaListElement + PartialEq,
+{
+    let mut diffs = Vec::new();
+
+    for i in 0..list.len().max(other.len()) {
+        match (list.get(i), other.get(i)) {
+            (Some(x), Some(y)) if x != y => diffs.push(ElementDiff::Changed(i)),
+            (Some(_), None) => diffs.push(ElementDiff::Removed(i)),
+            (None, Some(_)) => diffs.push(ElementDiff::Added(i)),
+            _ => (),
+        }
+    }
+
+    diffs
+}
+
+impl BeatmapLevel {
+    /// List the sections differing between this beatmap and another one, in file order.
+    pub fn diff(&self, other: &BeatmapLevel) -> Vec<SectionDiff> {
+        let mut diffs = Vec::new();
+
+        if self.general != other.general {
+            diffs.push(SectionDiff::General);
+        }
+        if self.editor != other.editor {
+            diffs.push(SectionDiff::Editor);
+        }
+        if self.metadata != other.metadata {
+            diffs.push(SectionDiff::Metadata);
+        }
+        if self.difficulty != other.difficulty {
+            diffs.push(SectionDiff::Difficulty);
+        }
+
+        let events = diff_list(&self.events, &other.events);
+        if !events.is_empty() {
+            diffs.push(SectionDiff::Events(events));
+        }
+        let timing_points = diff_list(&self.timing_points, &other.timing_points);
+        if !timing_points.is_empty() {
+            diffs.push(SectionDiff::TimingPoints(timing_points));
+        }
+
+        if self.colours != other.colours {
+            diffs.push(SectionDiff::Colours);
+        }
+
+        let hit_objects = diff_list(&self.hit_objects, &other.hit_objects);
+        if !hit_objects.is_empty() {
+            diffs.push(SectionDiff::HitObjects(hit_objects));
+        }
+
+        if self.unknown_sections != other.unknown_sections {
+            diffs.push(SectionDiff::UnknownSections);
+        }
+
+        diffs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::{ElementDiff, SectionDiff};
+    use crate::BeatmapLevel;
+    use std::path::Path;
+
+    const TEST_BEATMAP_LEVEL_PATH: &str = "./assets/examples/test.osu";
+
+    #[test]
+    fn diff_same_beatmap() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let other = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+
+        assert!(beatmap_level.diff(&other).is_empty());
+    }
+
+    #[test]
+    fn diff_changed_hit_object() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let mut other = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        other.hit_objects[4].x += 10;
+
+        assert_eq!(
+            beatmap_level.diff(&other),
+            vec![SectionDiff::HitObjects(vec![ElementDiff::Changed(4)])]
+        );
+    }
+
+    #[test]
+    fn diff_added_removed_hit_objects() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let mut other = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let last = other.hit_objects.len() - 1;
+        other.hit_objects.pop();
+
+        assert_eq!(
+            beatmap_level.diff(&other),
+            vec![SectionDiff::HitObjects(vec![ElementDiff::Removed(last)])]
+        );
+        assert_eq!(
+            other.diff(&beatmap_level),
+            vec![SectionDiff::HitObjects(vec![ElementDiff::Added(last)])]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::types::general::Gamemode;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -18,6 +19,10 @@ pub enum BeatmapParseError {
     StoryboardEntry,
     #[error("The section seems to not be present in the beatmap file")]
     SectionNotFound { section: String },
+    #[error("Invalid combo colour index in '{line}', expected Combo1 to Combo8")]
+    InvalidComboIndex { line: String },
+    #[error("The beatmap is for the {found:?} gamemode, expected {expected:?}")]
+    UnexpectedGamemode { expected: Gamemode, found: Gamemode },
 }
 
 #[derive(Error, Debug)]
@@ -36,8 +41,19 @@ pub enum GeneralError {
     UnexpectedSampleSetValue { value: String },
 }
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, PartialEq, Eq)]
 pub enum EventsError {
     #[error("Tried to read an unknown type in the Events section, got {value}, expected 'Background', 'Video', 'Break' or 0-1-2.")]
     UnexpectedEventType { value: String },
+    #[error("Tried to convert an event to the wrong type, expected {expected}, found {found}")]
+    EventTypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DirectoryError {
+    #[error("No difficulty named '{version}' was found in the beatmap directory")]
+    DifficultyNotFound { version: String },
 }
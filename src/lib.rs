@@ -1,24 +1,40 @@
-use crate::error::BeatmapParseError;
+use crate::error::{BeatmapParseError, DirectoryError};
+use crate::options::{ParseOptions, SerializeOptions};
 use crate::section::colours::Colours;
 use crate::section::difficulty::DifficultySection;
 use crate::section::editor::EditorSection;
-use crate::section::events::Event;
+use crate::section::events::{BackgroundParams, Event, EventType};
 use crate::section::general::GeneralSection;
-use crate::section::hit_objects::HitObject;
+use crate::section::hit_objects::{HitObject, HitObjectType, SliderContext, SLIDER_PATH_SPACING};
 use crate::section::metadata::MetadataSection;
+use crate::section::storyboard::Storyboard;
 use crate::section::timing_points::TimingPoint;
-use crate::section::CommaListOf;
+use crate::section::{split_sections, CommaListOf, KeyStyles, SectionId};
+use crate::types::difficulty::DiffMod;
+use crate::types::general::Gamemode;
+use crate::types::timing_points::BeatLength;
+use crate::types::SampleSet;
 use crate::BeatmapParseError::SectionNotFound;
+use std::collections::hash_map::DefaultHasher;
 use std::error::Error;
+use std::ffi::OsStr;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::Read;
+use std::ops::Range;
 use std::path::Path;
 use std::str::FromStr;
 use std::{fs, io};
 
-mod error;
+pub mod builder;
+pub mod diff;
+pub mod error;
+pub mod options;
 pub mod section;
+pub mod summary;
 pub mod types;
+pub mod validation;
+pub mod view;
 
 #[derive(Debug, Default)]
 pub struct BeatmapLevel {
@@ -27,9 +43,17 @@ pub struct BeatmapLevel {
     pub metadata: MetadataSection,
     pub difficulty: DifficultySection,
     pub events: CommaListOf<Event>,
+    /// Storyboard entries of the `[Events]` section.
+    pub storyboard: Storyboard,
     pub timing_points: CommaListOf<TimingPoint>,
     pub colours: Colours,
     pub hit_objects: CommaListOf<HitObject>,
+    /// Sections not handled by the library, as `(name, content)` pairs in file order.
+    /// They are kept as is and written back after the known sections.
+    pub unknown_sections: Vec<(String, String)>,
+    /// Casing and spacing of the keys of the `key:value` sections as written in the parsed file,
+    /// kept when serializing the beatmap.
+    pub key_styles: KeyStyles,
 }
 
 impl BeatmapLevel {
@@ -40,12 +64,894 @@ impl BeatmapLevel {
     pub fn parse(str: &str) -> Result<Self, BeatmapParseError> {
         Self::from_str(str)
     }
+
+    /// Parse a beatmap, calling `on_section` after each section is parsed to report the progress
+    /// of the parsing of big beatmaps. Sections are parsed in the order of [SectionId::ALL].
+    ///
+    /// `on_section` is only called for the sections present in the beatmap, the optional
+    /// `[TimingPoints]` and `[Colours]` sections default to empty ones without being reported.
+    pub fn parse_streaming(
+        s: &str,
+        on_section: impl FnMut(SectionId),
+    ) -> Result<Self, BeatmapParseError> {
+        Self::parse_sections(s, &ParseOptions::default(), on_section)
+    }
+
+    /// Parse a beatmap with the given options.
+    pub fn parse_with(s: &str, options: &ParseOptions) -> Result<Self, BeatmapParseError> {
+        Self::parse_sections(s, options, |_| ())
+    }
+
+    /// Version of the format of a beatmap file, read from its first line such as
+    /// `osu file format v14`. The line is read tolerantly, ignoring the case and any whitespace
+    /// around the version, and `14` is returned if it is missing or malformed.
+    pub fn parse_format_version(s: &str) -> u32 {
+        const DEFAULT_VERSION: u32 = 14;
+
+        let line = s
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .map(|x| x.trim())
+            .find(|x| !x.is_empty())
+            .unwrap_or_default();
+
+        if !line.to_lowercase().starts_with("osu file format") {
+            return DEFAULT_VERSION;
+        }
+        line.rfind(['v', 'V'])
+            .and_then(|i| line[i + 1..].trim().parse().ok())
+            .unwrap_or(DEFAULT_VERSION)
+    }
+
+    /// Parse only the `[Metadata]` section of a beatmap, without parsing the other sections.
+    /// The other sections are only scanned for their headers, and as with [BeatmapLevel::parse]
+    /// a repeated `[Metadata]` section overrides the previous one.
+    pub fn parse_metadata_only(s: &str) -> Result<MetadataSection, BeatmapParseError> {
+        split_sections(s)
+            .filter(|(name, _)| *name == SectionId::Metadata.name())
+            .last()
+            .ok_or_else(|| SectionNotFound {
+                section: SectionId::Metadata.name().to_string(),
+            })?
+            .1
+            .parse()
+    }
+
+    fn parse_sections(
+        s: &str,
+        options: &ParseOptions,
+        mut on_section: impl FnMut(SectionId),
+    ) -> Result<Self, BeatmapParseError> {
+        let mut general = None;
+        let mut editor = None;
+        let mut metadata = None;
+        let mut difficulty = None;
+        let mut events = None;
+        let mut timing_points = None;
+        let mut colours = None;
+        let mut hit_objects = None;
+        let mut unknown_sections = Vec::new();
+
+        for (name, content) in split_sections(s) {
+            let slot = match name {
+                "General" => &mut general,
+                "Editor" => &mut editor,
+                "Metadata" => &mut metadata,
+                "Difficulty" => &mut difficulty,
+                "Events" => &mut events,
+                "TimingPoints" => &mut timing_points,
+                "Colours" => &mut colours,
+                "HitObjects" => &mut hit_objects,
+                _ => {
+                    // Line endings are restored when serializing, see `SerializeOptions::crlf`.
+                    unknown_sections.push((name.to_string(), content.replace("\r\n", "\n")));
+                    continue;
+                }
+            };
+            // A repeated section overrides the previous one.
+            *slot = Some(content);
+        }
+
+        fn section<'a>(content: Option<&'a str>, name: &str) -> Result<&'a str, BeatmapParseError> {
+            content.ok_or_else(|| SectionNotFound {
+                section: name.to_string(),
+            })
+        }
+
+        fn parse_section<T>(
+            content: Option<&str>,
+            id: SectionId,
+            parse: impl FnOnce(&str) -> Result<T, BeatmapParseError>,
+            on_section: &mut impl FnMut(SectionId),
+        ) -> Result<T, BeatmapParseError> {
+            let parsed = parse(section(content, id.name())?)?;
+            on_section(id);
+            Ok(parsed)
+        }
+
+        fn parse_optional_section<T: Default>(
+            content: Option<&str>,
+            id: SectionId,
+            parse: impl FnOnce(&str) -> Result<T, BeatmapParseError>,
+            on_section: &mut impl FnMut(SectionId),
+        ) -> Result<T, BeatmapParseError> {
+            match content {
+                Some(_) => parse_section(content, id, parse, on_section),
+                None => Ok(T::default()),
+            }
+        }
+
+        // The storyboard is part of the Events section, parsed before reporting it.
+        let storyboard = section(events, SectionId::Events.name())?.parse()?;
+
+        let on_section = &mut on_section;
+
+        let mut beatmap_level = BeatmapLevel {
+            general: parse_section(
+                general,
+                SectionId::General,
+                |x| GeneralSection::parse_with(x, options),
+                on_section,
+            )?,
+            editor: parse_section(
+                editor,
+                SectionId::Editor,
+                |x| EditorSection::parse_with(x, options),
+                on_section,
+            )?,
+            metadata: parse_section(
+                metadata,
+                SectionId::Metadata,
+                |x| MetadataSection::parse_with(x, options),
+                on_section,
+            )?,
+            difficulty: parse_section(
+                difficulty,
+                SectionId::Difficulty,
+                |x| DifficultySection::parse_with(x, options),
+                on_section,
+            )?,
+            events: parse_section(events, SectionId::Events, str::parse, on_section)?,
+            storyboard,
+            timing_points: parse_optional_section(
+                timing_points,
+                SectionId::TimingPoints,
+                str::parse,
+                on_section,
+            )?,
+            // osu! doesn't write the colours when there are none
+            colours: parse_optional_section(colours, SectionId::Colours, str::parse, on_section)?,
+            hit_objects: parse_section(
+                hit_objects,
+                SectionId::HitObjects,
+                |x| CommaListOf::<HitObject>::parse_with(x, options),
+                on_section,
+            )?,
+            unknown_sections,
+            key_styles: KeyStyles::new(options),
+        };
+
+        for (id, content) in [
+            (SectionId::General, general),
+            (SectionId::Editor, editor),
+            (SectionId::Metadata, metadata),
+            (SectionId::Difficulty, difficulty),
+        ] {
+            beatmap_level
+                .key_styles
+                .capture(id, content.unwrap_or_default());
+        }
+
+        // Very old beatmaps store the bookmarks in the General section, they are moved to the
+        // Editor section where they are written back.
+        if beatmap_level.editor.bookmarks.is_empty() {
+            beatmap_level.editor.bookmarks = EditorSection::legacy_bookmarks(
+                section(general, SectionId::General.name())?,
+                options,
+            )?;
+        }
+
+        Ok(beatmap_level)
+    }
+
+    /// Parse a beatmap from raw bytes, replacing invalid UTF-8 sequences
+    /// with `U+FFFD REPLACEMENT CHARACTER` instead of failing.
+    ///
+    /// Lossy decoding may alter text fields containing non UTF-8 bytes, such as filenames,
+    /// which then won't match the files on disk anymore.
+    pub fn parse_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::from_str(&String::from_utf8_lossy(bytes))?)
+    }
+
+    /// Parse a beatmap from raw bytes in a legacy encoding (Windows-1252, Shift_JIS...),
+    /// transcoding them to UTF-8 first.
+    ///
+    /// A byte order mark at the start of the bytes overrides the given encoding.
+    /// Malformed sequences are replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    #[cfg(feature = "encoding_rs")]
+    pub fn from_bytes_with_encoding(
+        bytes: &[u8],
+        encoding: &'static encoding_rs::Encoding,
+    ) -> Result<Self, BeatmapParseError> {
+        let (decoded, _, _) = encoding.decode(bytes);
+        Self::from_str(&decoded)
+    }
+
     pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
         Ok(path.try_into()?)
     }
+
+    /// Open the difficulty with the given name (`Version` in the metadata) from a beatmap
+    /// directory containing multiple `.osu` files. Files which can't be parsed are ignored.
+    pub fn open_difficulty(dir: &Path, version: &str) -> Result<Self, Box<dyn Error>> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.extension() != Some(OsStr::new("osu")) {
+                continue;
+            }
+
+            if let Ok(beatmap) = Self::open(&path) {
+                if beatmap.metadata.version == version {
+                    return Ok(beatmap);
+                }
+            }
+        }
+
+        Err(DirectoryError::DifficultyNotFound {
+            version: version.to_string(),
+        })?
+    }
+
     pub fn save(&self, path: &Path) -> io::Result<()> {
         Ok(fs::write(path, self.to_string())?)
     }
+
+    /// Save the beatmap serialized with the given options.
+    pub fn save_with(&self, path: &Path, options: &SerializeOptions) -> io::Result<()> {
+        fs::write(path, self.to_string_with(options))
+    }
+
+    /// Serialize the beatmap with the given options.
+    pub fn to_string_with(&self, options: &SerializeOptions) -> String {
+        let mut buf = String::from("osu file format v14\n\n");
+
+        for id in SectionId::ALL {
+            let omitted = match id {
+                SectionId::TimingPoints => {
+                    options.omit_empty_timing_points && self.timing_points.is_empty()
+                }
+                SectionId::Colours => self.colours.is_empty(),
+                _ => false,
+            };
+            if omitted {
+                continue;
+            }
+            buf.push_str(&format!(
+                "[{}]\n{}",
+                id.name(),
+                self.section_content(id, options)
+            ));
+            match id {
+                SectionId::HitObjects => {}
+                // osu! writes an additional blank line after the timing points
+                SectionId::TimingPoints => buf.push_str("\n\n"),
+                _ => buf.push('\n'),
+            }
+        }
+
+        for (name, content) in self.unknown_sections.iter() {
+            buf.push_str(&format!("\n[{}]\n{}\n", name, content));
+        }
+
+        match options.crlf {
+            true => buf.replace('\n', "\r\n"),
+            false => buf,
+        }
+    }
+
+    /// Serialize the beatmap as the osu! editor would save it, see [SerializeOptions::editor].
+    pub fn to_editor_string(&self) -> String {
+        self.to_string_with(&SerializeOptions::editor())
+    }
+
+    /// Serialize only the given sections, in the given order, each with its header and
+    /// separated by a blank line.
+    pub fn serialize_sections(&self, sections: &[SectionId]) -> String {
+        let options = SerializeOptions::default();
+
+        sections
+            .iter()
+            .map(|x| format!("[{}]\n{}", x.name(), self.section_content(*x, &options)))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Serialized content of a section, without its header.
+    fn section_content(&self, id: SectionId, options: &SerializeOptions) -> String {
+        let styled = |content: String| self.key_styles.apply(id, content);
+
+        match id {
+            SectionId::General => styled(self.general.serialize_with(options)),
+            SectionId::Editor => styled(self.editor.serialize_with(options)),
+            SectionId::Metadata => styled(self.metadata.serialize_with(options)),
+            SectionId::Difficulty => styled(self.difficulty.serialize_with(options)),
+            SectionId::Events => self.events.to_stable_string(&self.storyboard),
+            SectionId::TimingPoints => self.timing_points.to_string(),
+            SectionId::Colours => self.colours.to_string(),
+            SectionId::HitObjects => self.hit_objects.serialize_with(options),
+        }
+    }
+
+    /// Hash of the gameplay content of the beatmap: its timing points and hit objects.
+    /// Editor settings, metadata and the other sections are ignored.
+    ///
+    /// The hash is computed with [DefaultHasher], so it shouldn't be persisted across
+    /// different builds of a program.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        self.timing_points.iter().for_each(|x| x.hash(&mut hasher));
+        self.hit_objects.iter().for_each(|x| x.hash(&mut hasher));
+
+        hasher.finish()
+    }
+
+    /// Whether two beatmaps play the same: same timing points, hit objects and difficulty
+    /// settings. Editor settings, metadata, colours and the other sections are ignored.
+    pub fn gameplay_eq(&self, other: &Self) -> bool {
+        self.timing_points == other.timing_points
+            && self.hit_objects == other.hit_objects
+            && self.difficulty == other.difficulty
+    }
+
+    /// Time of the first hit object, if any.
+    pub fn first_object_time(&self) -> Option<i32> {
+        self.hit_objects.iter().map(|x| x.time).min()
+    }
+
+    /// Time when the last hit object ends, if any.
+    pub fn last_object_time(&self) -> Option<i32> {
+        self.hit_objects
+            .iter()
+            .map(|x| self.object_end_time(x))
+            .max()
+    }
+
+    /// Iterate over the uninherited (red) timing points, which set the BPM.
+    pub fn uninherited_points(&self) -> impl Iterator<Item = &TimingPoint> {
+        self.timing_points
+            .iter()
+            .filter(|x| bool::from(x.is_uninherited))
+    }
+
+    /// Iterate over the inherited (green) timing points, which change the slider velocity.
+    pub fn inherited_points(&self) -> impl Iterator<Item = &TimingPoint> {
+        self.timing_points
+            .iter()
+            .filter(|x| !bool::from(x.is_uninherited))
+    }
+
+    /// Lowest and highest BPM of the uninherited timing points, if any.
+    pub fn bpm_range(&self) -> Option<(f64, f64)> {
+        self.uninherited_points()
+            .filter_map(|x| x.beat_length.as_bpm())
+            .fold(None, |range, bpm| match range {
+                None => Some((bpm, bpm)),
+                Some((min, max)) => Some((f64::min(min, bpm), f64::max(max, bpm))),
+            })
+    }
+
+    /// BPM covering the most time of the beatmap, which is the one displayed by osu!.
+    ///
+    /// Like osu!, the first uninherited timing point is considered to start at `0` and timing
+    /// sections ending after the last hit object are cut at its end.
+    pub fn dominant_bpm(&self) -> Option<f64> {
+        let uninherited: Vec<&TimingPoint> = self
+            .timing_points
+            .iter()
+            .filter(|x| bool::from(x.is_uninherited) && x.beat_length.as_bpm().is_some())
+            .collect();
+        let end = self.last_object_time().unwrap_or(uninherited.last()?.time);
+
+        let mut durations: Vec<(BeatLength, u32)> = Vec::new();
+        for (i, point) in uninherited.iter().enumerate() {
+            let start = if i == 0 { 0 } else { point.time };
+            let next = uninherited.get(i + 1).map_or(end, |x| x.time.min(end));
+            let duration = u32::try_from(next - start).unwrap_or(0);
+
+            match durations.iter_mut().find(|(x, _)| *x == point.beat_length) {
+                Some((_, total)) => *total += duration,
+                None => durations.push((point.beat_length, duration)),
+            }
+        }
+
+        durations
+            .iter()
+            .rev()
+            .max_by_key(|(_, duration)| *duration)
+            .and_then(|(beat_length, _)| beat_length.as_bpm())
+    }
+
+    /// Distance travelled over all the sliders of the beatmap, in osu! pixels.
+    /// See [SliderParams::total_length](crate::section::hit_objects::SliderParams::total_length).
+    pub fn total_slider_distance(&self) -> f64 {
+        self.hit_objects
+            .iter()
+            .filter_map(|x| match &x.object_params {
+                HitObjectType::Slider(params) => Some(params.total_length()),
+                _ => None,
+            })
+            .sum()
+    }
+
+    /// Time when a hit object ends, taking into account the timing of the beatmap for sliders.
+    pub fn object_end_time(&self, hit_object: &HitObject) -> i32 {
+        match &hit_object.object_params {
+            HitObjectType::Slider(params) => match self.slider_context_at(hit_object.time) {
+                Some(ctx) => {
+                    let duration = params.span_duration(&ctx) * params.slides as f64;
+                    match duration.is_finite() && duration > 0. {
+                        true => hit_object.time + duration.round() as i32,
+                        false => hit_object.time,
+                    }
+                }
+                None => hit_object.time,
+            },
+            _ => hit_object.end_time(),
+        }
+    }
+
+    /// Distance in osu! pixels and time in milliseconds between each pair of consecutive hit
+    /// objects, as `(distance, time_delta)`. The gap starts at the end of the first object:
+    /// its end time, and its end position, see [HitObject::slider_end_position] for sliders.
+    ///
+    /// Objects sharing their time have a delta of 0, see [BeatmapLevel::object_velocities] to
+    /// divide the distances by the deltas without checking for it.
+    pub fn object_spacings(&self) -> Vec<(f64, i32)> {
+        self.hit_objects
+            .windows(2)
+            .map(|pair| {
+                let (from, to) = (&pair[0], &pair[1]);
+                let (x, y) = from
+                    .slider_end_position()
+                    .unwrap_or((f64::from(from.x), f64::from(from.y)));
+                let distance = (f64::from(to.x) - x).hypot(f64::from(to.y) - y);
+
+                (distance, to.time - self.object_end_time(from))
+            })
+            .collect()
+    }
+
+    /// Velocity in osu! pixels per millisecond between each pair of consecutive hit objects,
+    /// the distance divided by the time delta of [BeatmapLevel::object_spacings].
+    ///
+    /// The velocity is `None` when the time delta isn't positive: for objects sharing their
+    /// time, or starting before the end of the previous object.
+    pub fn object_velocities(&self) -> Vec<Option<f64>> {
+        self.object_spacings()
+            .into_iter()
+            .map(|(distance, time_delta)| match time_delta > 0 {
+                true => Some(distance / f64::from(time_delta)),
+                false => None,
+            })
+            .collect()
+    }
+
+    /// Timing context of a slider starting at the given time.
+    /// Objects placed before the first uninherited timing point use it anyway, like osu! does.
+    ///
+    /// Returns `None` if the beatmap has no uninherited timing point.
+    pub fn slider_context_at(&self, time: i32) -> Option<SliderContext> {
+        let uninherited = self
+            .uninherited_points()
+            .take_while(|x| x.time <= time)
+            .last()
+            .or_else(|| self.uninherited_points().next())?;
+        let inherited = self
+            .timing_points
+            .iter()
+            .take_while(|x| x.time <= time)
+            .last()
+            .filter(|x| !bool::from(x.is_uninherited));
+
+        Some(SliderContext::new(uninherited, inherited, &self.difficulty))
+    }
+
+    /// Sample set, custom sample index and volume active at the given time, from the last timing
+    /// point, inherited or not, at or before it. Times before the first timing point use it,
+    /// and a beatmap without timing points gives osu!'s defaults at full volume.
+    pub fn sample_state_at(&self, time: i32) -> (SampleSet, u32, u8) {
+        let index = self.timing_points.partition_point(|x| x.time <= time);
+
+        match self.timing_points.get(index.saturating_sub(1)) {
+            Some(x) => (x.sample_set, x.sample_index, x.volume),
+            None => (SampleSet::Default, 0, 100),
+        }
+    }
+
+    /// Iterate over the hit objects paired with the uninherited timing point governing them.
+    ///
+    /// Both lists are expected to be sorted by time, as they are in beatmap files.
+    /// Hit objects placed before the first uninherited timing point are skipped.
+    pub fn objects_with_timing(&self) -> impl Iterator<Item = (&HitObject, &TimingPoint)> {
+        let uninherited: Vec<&TimingPoint> = self.uninherited_points().collect();
+
+        self.hit_objects.iter().filter_map(move |hit_object| {
+            let index = uninherited.partition_point(|x| x.time <= hit_object.time);
+            index.checked_sub(1).map(|i| (hit_object, uninherited[i]))
+        })
+    }
+
+    /// Iterate over the hit objects whose time is within `start..=end`.
+    ///
+    /// The hit objects are expected to be sorted by time, as they are in beatmap files.
+    pub fn hit_objects_between(&self, start: i32, end: i32) -> impl Iterator<Item = &HitObject> {
+        let range = self.hit_objects_range(start, end);
+        self.hit_objects[range].iter()
+    }
+
+    /// Mutable variant of [BeatmapLevel::hit_objects_between], to edit a selection of objects.
+    pub fn hit_objects_between_mut(
+        &mut self,
+        start: i32,
+        end: i32,
+    ) -> impl Iterator<Item = &mut HitObject> {
+        let range = self.hit_objects_range(start, end);
+        self.hit_objects[range].iter_mut()
+    }
+
+    fn hit_objects_range(&self, start: i32, end: i32) -> Range<usize> {
+        let lower = self.hit_objects.partition_point(|x| x.time < start);
+        let upper = self.hit_objects.partition_point(|x| x.time <= end);
+        lower..upper.max(lower)
+    }
+
+    /// Indices of the hit objects sharing the same time, such as chords in mania or stacked
+    /// notes, grouped by time. Objects alone at their time aren't returned.
+    ///
+    /// The hit objects are expected to be sorted by time, as they are in beatmap files.
+    pub fn simultaneous_objects(&self) -> Vec<Vec<usize>> {
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut current: Vec<usize> = Vec::new();
+
+        for (i, hit_object) in self.hit_objects.iter().enumerate() {
+            if let Some(&last) = current.last() {
+                if self.hit_objects[last].time != hit_object.time {
+                    if current.len() > 1 {
+                        groups.push(current);
+                    }
+                    current = Vec::new();
+                }
+            }
+            current.push(i);
+        }
+        if current.len() > 1 {
+            groups.push(current);
+        }
+
+        groups
+    }
+
+    /// Check that the beatmap is for the `expected` gamemode, to reject beatmaps of other
+    /// gamemodes early.
+    pub fn assert_mode(&self, expected: Gamemode) -> Result<(), BeatmapParseError> {
+        match self.general.mode == expected {
+            true => Ok(()),
+            false => Err(BeatmapParseError::UnexpectedGamemode {
+                expected,
+                found: self.general.mode,
+            }),
+        }
+    }
+
+    /// Position and time `(x, y, time)` of each hit object, in the order of the hit objects.
+    ///
+    /// With `slider_paths`, sliders are replaced by points sampled along their path, from their
+    /// head, every [SLIDER_PATH_SPACING] osu! pixels (see [HitObject::slider_path]). The points
+    /// are rounded to the closest osu! pixel and are all at the time of the slider.
+    pub fn positions(&self, slider_paths: bool) -> Vec<(i32, i32, i32)> {
+        let mut positions = Vec::with_capacity(self.hit_objects.len());
+
+        for hit_object in self.hit_objects.iter() {
+            match slider_paths
+                .then(|| hit_object.slider_path(SLIDER_PATH_SPACING))
+                .flatten()
+            {
+                Some(path) => positions.extend(
+                    path.iter()
+                        .map(|x| (x.0.round() as i32, x.1.round() as i32, hit_object.time)),
+                ),
+                None => positions.push((hit_object.x, hit_object.y, hit_object.time)),
+            }
+        }
+
+        positions
+    }
+
+    /// Insert a hit object, keeping the hit objects sorted by time.
+    /// Objects at the same time are kept in insertion order.
+    pub fn add_hit_object(&mut self, hit_object: HitObject) {
+        self.hit_objects.insert_sorted(hit_object);
+    }
+
+    /// Insert a timing point, keeping the timing points sorted by time.
+    /// Timing points at the same time are kept in insertion order.
+    pub fn add_timing_point(&mut self, timing_point: TimingPoint) {
+        self.timing_points.insert_sorted(timing_point);
+    }
+
+    /// Fix the new combo flags of the hit objects after they were edited or reordered,
+    /// keeping the existing flags otherwise:
+    /// - the first hit object always starts a new combo, and its combo colour skip is cleared
+    ///   (see [BeatmapLevel::normalize_first_combo]),
+    /// - a hit object following a spinner always starts a new combo, as osu! does implicitly.
+    pub fn normalize_combos(&mut self) {
+        let mut previous_is_spinner = true;
+
+        for hit_object in self.hit_objects.iter_mut() {
+            if previous_is_spinner {
+                hit_object.new_combo = true;
+            }
+
+            previous_is_spinner = matches!(hit_object.object_params, HitObjectType::Spinner(_));
+        }
+
+        self.normalize_first_combo();
+    }
+
+    /// Make the first hit object a plain new combo.
+    ///
+    /// osu! always starts a new combo on the first hit object whatever its flags, but a combo
+    /// colour skip written on it is still applied when the beatmap is re-imported, so it is
+    /// removed here.
+    pub fn normalize_first_combo(&mut self) {
+        if let Some(first) = self.hit_objects.first_mut() {
+            first.new_combo = true;
+            first.combo_skip = 0;
+        }
+    }
+
+    /// Whether the hit object at the given index starts a new combo when played.
+    ///
+    /// Unlike the `new_combo` flag, this takes into account the combos osu! starts implicitly:
+    /// on the first hit object and on a hit object following a spinner.
+    /// `None` is returned when there is no hit object at the given index.
+    pub fn starts_new_combo(&self, index: usize) -> Option<bool> {
+        let hit_object = self.hit_objects.get(index)?;
+
+        Some(match index.checked_sub(1) {
+            None => true,
+            Some(previous) => {
+                matches!(
+                    self.hit_objects[previous].object_params,
+                    HitObjectType::Spinner(_)
+                ) || hit_object.new_combo
+            }
+        })
+    }
+
+    /// Combo number shown on each hit object, starting from 1 on each new combo, including the
+    /// implicit ones of [BeatmapLevel::starts_new_combo]. Spinners don't increment the combo
+    /// number, they keep the one of the previous object unless they start a new combo.
+    pub fn combo_numbers(&self) -> Vec<u32> {
+        let mut number = 0;
+
+        (0..self.hit_objects.len())
+            .map(|i| {
+                if self.starts_new_combo(i) == Some(true) {
+                    number = 1;
+                } else if !matches!(self.hit_objects[i].object_params, HitObjectType::Spinner(_)) {
+                    number += 1;
+                }
+                number
+            })
+            .collect()
+    }
+
+    /// Apply a mod to the beatmap, changing the difficulty settings and,
+    /// for [HardRock](DiffMod::HardRock), flipping the hit objects vertically.
+    pub fn apply_mod(&mut self, diff_mod: DiffMod) {
+        self.difficulty.apply_mod(diff_mod);
+
+        if diff_mod == DiffMod::HardRock {
+            self.flip_vertical();
+        }
+    }
+
+    /// Replace the timing points of the beatmap with a copy of the ones of another difficulty,
+    /// also copying its slider multiplier if `slider_multiplier` is set.
+    pub fn copy_timing_from(&mut self, other: &BeatmapLevel, slider_multiplier: bool) {
+        self.timing_points = other.timing_points.clone();
+
+        if slider_multiplier {
+            self.difficulty.slider_multiplier = other.difficulty.slider_multiplier;
+        }
+    }
+
+    /// Whether the first timing point is uninherited, which osu! needs to know the BPM of the
+    /// beatmap. A beatmap without timing points isn't valid either.
+    pub fn has_valid_initial_timing(&self) -> bool {
+        self.timing_points
+            .first()
+            .is_some_and(|x| bool::from(x.is_uninherited))
+    }
+
+    /// Insert an uninherited timing point at the start of the beatmap if it doesn't start with
+    /// one, see [BeatmapLevel::has_valid_initial_timing].
+    ///
+    /// The actual BPM of the song can't be known from the beatmap, so the point uses
+    /// `default_bpm` with a 4/4 meter. It is placed at the first hit object, or at the first
+    /// timing point if it comes earlier, and takes the samples of the first timing point.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `default_bpm` isn't a finite positive number, as it gives no valid beat length.
+    pub fn repair_initial_timing(&mut self, default_bpm: f64) {
+        assert!(
+            default_bpm.is_finite() && default_bpm > 0.,
+            "default_bpm must be a finite positive number, got {}",
+            default_bpm
+        );
+        if self.has_valid_initial_timing() {
+            return;
+        }
+
+        let time = [
+            self.first_object_time(),
+            self.timing_points.first().map(|x| x.time),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(0);
+        let mut timing_point = TimingPoint {
+            time,
+            beat_length: BeatLength::from(60000. / default_bpm),
+            meter: 4,
+            volume: 100,
+            is_uninherited: true.into(),
+            ..Default::default()
+        };
+        if let Some(first) = self.timing_points.first() {
+            timing_point.sample_set = first.sample_set;
+            timing_point.sample_index = first.sample_index;
+            timing_point.volume = first.volume;
+        }
+
+        // Placed first even if an inherited point shares its time, as osu! expects
+        self.timing_points.insert(0, timing_point);
+    }
+
+    /// Remove the inherited timing points which don't change anything compared to the timing
+    /// point preceding them: same sample set, sample index, volume, slider velocity and effects.
+    /// Uninherited timing points are never removed.
+    pub fn remove_redundant_timing_points(&mut self) {
+        let timing_points: Vec<TimingPoint> = self.timing_points.drain(..).collect();
+
+        for timing_point in timing_points {
+            if !bool::from(timing_point.is_uninherited) {
+                if let Some(previous) = self.timing_points.last() {
+                    if previous.sample_set == timing_point.sample_set
+                        && previous.sample_index == timing_point.sample_index
+                        && previous.volume == timing_point.volume
+                        && previous.effects == timing_point.effects
+                        && previous.slider_velocity() == timing_point.slider_velocity()
+                    {
+                        continue;
+                    }
+                }
+            }
+
+            self.timing_points.push(timing_point);
+        }
+    }
+
+    /// Flip every hit object across the horizontal axis of the playfield (`y -> 384 - y`).
+    pub fn flip_vertical(&mut self) {
+        self.hit_objects
+            .iter_mut()
+            .for_each(|x| x.mirror_vertical());
+    }
+
+    /// Reset the hitsounds and samples of every hit object to their defaults.
+    pub fn clear_hitsounds(&mut self) {
+        self.hit_objects
+            .iter_mut()
+            .for_each(|x| x.clear_hitsounds());
+    }
+
+    /// Replace the filename of the audio file of the beatmap.
+    ///
+    /// Only `AudioFilename` in `[General]` is changed, storyboard entries and samples referencing
+    /// the previous audio file are left untouched.
+    pub fn set_audio_filename(&mut self, name: &str) {
+        self.general.audio_filename = name.to_string();
+    }
+
+    /// Filename of the background image, if the beatmap has one.
+    pub fn background_filename(&self) -> Option<&str> {
+        self.events.iter().find_map(|x| match &x.event_params {
+            EventType::Background(params) => Some(params.filename.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Break periods of the beatmap, as `(start_time, end_time)` pairs.
+    pub fn breaks(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.events.iter().filter_map(|x| match &x.event_params {
+            EventType::Break(params) => Some((x.start_time, params.end_time)),
+            _ => None,
+        })
+    }
+
+    /// Kiai time sections of the beatmap, as `[start_time, end_time)` pairs.
+    ///
+    /// Kiai starts at a timing point with the kiai effect and ends at the next timing point
+    /// without it. A section still active at the last timing point ends with the last hit object.
+    pub fn kiai_sections(&self) -> Vec<(i32, i32)> {
+        let mut sections = Vec::new();
+        let mut start: Option<i32> = None;
+
+        for timing_point in self.timing_points.iter() {
+            match (start, timing_point.is_kiai()) {
+                (None, true) => start = Some(timing_point.time),
+                (Some(x), false) => {
+                    sections.push((x, timing_point.time));
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(x) = start {
+            let end = self.last_object_time().unwrap_or(x).max(x);
+            sections.push((x, end));
+        }
+
+        sections
+    }
+
+    /// Filename of the background video, if the beatmap has one.
+    pub fn video_filename(&self) -> Option<&str> {
+        self.events.iter().find_map(|x| match &x.event_params {
+            EventType::Video(params) => Some(params.filename.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Filename of the visual displayed behind the gameplay: the video if there is one,
+    /// as osu! shows it over the background image, otherwise the background image.
+    pub fn main_visual_filename(&self) -> Option<&str> {
+        self.video_filename().or_else(|| self.background_filename())
+    }
+
+    /// Replace the filename of the background image.
+    /// If the beatmap has no background yet, one is inserted at `start_time = 0`.
+    pub fn set_background(&mut self, filename: &str) {
+        let background = self
+            .events
+            .iter_mut()
+            .find_map(|x| match &mut x.event_params {
+                EventType::Background(params) => Some(params),
+                _ => None,
+            });
+
+        match background {
+            Some(params) => params.filename = filename.to_string(),
+            None => self.events.insert(
+                0,
+                Event {
+                    start_time: 0,
+                    event_params: BackgroundParams {
+                        filename: filename.to_string(),
+                        ..Default::default()
+                    }
+                    .into(),
+                },
+            ),
+        }
+    }
 }
 
 impl TryFrom<File> for BeatmapLevel {
@@ -66,116 +972,1051 @@ impl TryFrom<&Path> for BeatmapLevel {
     }
 }
 
+impl TryFrom<&str> for BeatmapLevel {
+    type Error = BeatmapParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::from_str(value)
+    }
+}
+
 impl FromStr for BeatmapLevel {
     type Err = BeatmapParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let general_index = s.find("[General]").ok_or_else(|| SectionNotFound {
-            section: "General".to_string(),
-        })?;
-        let editor_index = s.find("[Editor]").ok_or_else(|| SectionNotFound {
-            section: "Editor".to_string(),
-        })?;
-        let metadata_index = s.find("[Metadata]").ok_or_else(|| SectionNotFound {
-            section: "Metadata".to_string(),
-        })?;
-        let difficulty_index = s.find("[Difficulty]").ok_or_else(|| SectionNotFound {
-            section: "Difficulty".to_string(),
-        })?;
-        let events_index = s.find("[Events]").ok_or_else(|| SectionNotFound {
-            section: "Events".to_string(),
-        })?;
-        let timing_points_index = s.find("[TimingPoints]").ok_or_else(|| SectionNotFound {
-            section: "TimingPoints".to_string(),
-        })?;
-        let colours_index = s.find("[Colours]").ok_or_else(|| SectionNotFound {
-            section: "Colours".to_string(),
-        })?;
-        let hit_objects_index = s.find("[HitObjects]").ok_or_else(|| SectionNotFound {
-            section: "HitObjects".to_string(),
-        })?;
-
-        let general_str = s[general_index..editor_index]
-            .strip_prefix("[General]")
-            .unwrap()
-            .trim();
-        let editor_str = s[editor_index..metadata_index]
-            .strip_prefix("[Editor]")
-            .unwrap()
-            .trim();
-        let metadata_str = s[metadata_index..difficulty_index]
-            .strip_prefix("[Metadata]")
-            .unwrap()
-            .trim();
-        let difficulty_str = s[difficulty_index..events_index]
-            .strip_prefix("[Difficulty]")
-            .unwrap()
-            .trim();
-        let events_str = s[events_index..timing_points_index]
-            .strip_prefix("[Events]")
-            .unwrap()
-            .trim();
-        let timing_points_str = s[timing_points_index..colours_index]
-            .strip_prefix("[TimingPoints]")
-            .unwrap()
-            .trim();
-        let colours_str = s[colours_index..hit_objects_index]
-            .strip_prefix("[Colours]")
-            .unwrap()
-            .trim();
-        let hit_objects_str = s[hit_objects_index..]
-            .strip_prefix("[HitObjects]")
-            .unwrap()
-            .trim();
-
-        Ok(BeatmapLevel {
-            general: general_str.parse()?,
-            editor: editor_str.parse()?,
-            metadata: metadata_str.parse()?,
-            difficulty: difficulty_str.parse()?,
-            events: events_str.parse()?,
-            timing_points: timing_points_str.parse()?,
-            colours: colours_str.parse()?,
-            hit_objects: hit_objects_str.parse()?,
-        })
+        Self::parse_streaming(s, |_| ())
     }
 }
 
 impl ToString for BeatmapLevel {
     fn to_string(&self) -> String {
-        format! {"osu file format v14\n\
-        \n\
-        [General]\n\
-        {}\n\
-        [Editor]\n\
-        {}\n\
-        [Metadata]\n\
-        {}\n\
-        [Difficulty]\n\
-        {}\n\
-        [Events]\n\
-        {}\n\
-        [TimingPoints]\n\
-        {}\n\
-        [Colours]\n\
-        {}\n\
-        [HitObjects]\n\
-        {}", self.general.to_string(), self.editor.to_string(), self.metadata.to_string(),
-        self.difficulty.to_string(), self.events.to_string(), self.timing_points.to_string(),
-        self.colours.to_string(), self.hit_objects.to_string()}
+        self.to_string_with(&SerializeOptions::default())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::error::BeatmapParseError;
+    use crate::options::{ParseOptions, SerializeOptions};
+    use crate::section::colours::Colours;
+    use crate::section::hit_objects::{
+        HitObject, HitObjectType, HitSample, HitSoundFlag, SliderParams, SLIDER_PATH_SPACING,
+    };
+    use crate::section::timing_points::TimingPoint;
+    use crate::section::{split_sections, CommaListOf, KeyStyles, SectionId};
+    use crate::types::difficulty::DiffMod;
+    use crate::types::general::Gamemode;
+    use crate::types::SampleSet;
     use crate::BeatmapLevel;
+    use std::collections::HashMap;
     use std::fs::File;
     use std::io::Read;
     use std::path::Path;
+    use std::{env, fs, process};
 
     const TEST_BEATMAP_LEVEL_PATH: &'static str = "./assets/examples/test.osu";
+    const STABLE_BEATMAP_LEVEL_PATH: &str = "./assets/examples/stable.osu";
+    const EDITOR_BEATMAP_LEVEL_PATH: &str = "./assets/examples/editor.osu";
     const OUTPUT_BEATMAP_LEVEL_PATH: &'static str = "./assets/examples/test_output.osu";
 
+    #[test]
+    fn apply_hard_rock() {
+        let mut beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        beatmap_level.difficulty.overall_difficulty = 7.0;
+        beatmap_level.apply_mod(DiffMod::HardRock);
+
+        let slider_params: &SliderParams = match &beatmap_level.hit_objects[0].object_params {
+            HitObjectType::Slider(x) => x,
+            _ => panic!("first object of the test beatmap should be a slider"),
+        };
+
+        assert_eq!(beatmap_level.difficulty.overall_difficulty, 9.8);
+        assert_eq!(beatmap_level.hit_objects[0].y, 384 - 82);
+        assert_eq!(slider_params.curve_points[0].y, 384 - 74);
+    }
+
+    #[test]
+    fn remove_redundant_timing_points() {
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.timing_points = "10000,333.33,4,2,0,100,1,0
+12000,-50,4,2,0,80,0,0
+13000,-50,4,2,0,80,0,0
+14000,-50,4,2,0,70,0,0
+"
+        .parse()
+        .unwrap();
+
+        beatmap_level.remove_redundant_timing_points();
+
+        assert_eq!(beatmap_level.timing_points.len(), 3);
+        assert_eq!(beatmap_level.timing_points[1].time, 12000);
+        assert_eq!(beatmap_level.timing_points[2].time, 14000);
+    }
+
+    #[test]
+    fn parse_bytes_with_invalid_utf8() {
+        let mut bytes = fs::read(TEST_BEATMAP_LEVEL_PATH).unwrap();
+        let tag_index = bytes.windows(4).position(|x| x == b"Fast").unwrap();
+        bytes.insert(tag_index + 1, 0xFF);
+
+        let beatmap_level = BeatmapLevel::parse_bytes(&bytes).unwrap();
+
+        assert_eq!(beatmap_level.metadata.tags.last().unwrap(), "F\u{FFFD}ast");
+        assert_eq!(beatmap_level.metadata.title, "Marble Soda");
+    }
+
+    #[test]
+    #[cfg(feature = "encoding_rs")]
+    fn parse_bytes_with_encoding() {
+        let s = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+        let artist_index = s.find("Artist:").unwrap() + "Artist:".len();
+        let mut bytes = s.into_bytes();
+        // "Beyoncé" in Windows-1252
+        bytes.splice(artist_index..artist_index, b"Beyonc\xE9 & ".iter().copied());
+
+        assert!(std::str::from_utf8(&bytes).is_err());
+
+        let beatmap_level =
+            BeatmapLevel::from_bytes_with_encoding(&bytes, encoding_rs::WINDOWS_1252).unwrap();
+
+        assert!(beatmap_level.metadata.artist.starts_with("Beyoncé & "));
+    }
+
+    #[test]
+    fn first_last_object_time() {
+        let mut beatmap_level = BeatmapLevel::new();
+
+        assert_eq!(beatmap_level.first_object_time(), None);
+        assert_eq!(beatmap_level.last_object_time(), None);
+
+        beatmap_level.difficulty.slider_multiplier = 1.5;
+        beatmap_level.timing_points = "10000,333.33,4,0,0,100,1,1".parse().unwrap();
+        beatmap_level.hit_objects = "256,192,11000,21,2,0:0:0:0:
+256,192,11200,8,12,12000,3:0:0:80:
+100,100,12600,6,1,B|200:200|250:200|250:200|300:150,2,310.123,2|1|2,0:0|0:0|0:2,0:0:0:0:"
+            .parse()
+            .unwrap();
+
+        assert_eq!(beatmap_level.first_object_time(), Some(11000));
+        assert_eq!(beatmap_level.last_object_time(), Some(13978));
+    }
+
+    #[test]
+    fn parse_out_of_order_sections() {
+        let beatmap_level = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+        let difficulty_start = beatmap_level.find("[Difficulty]").unwrap();
+        let difficulty_end = beatmap_level.find("[Events]").unwrap();
+        let difficulty = &beatmap_level[difficulty_start..difficulty_end];
+
+        let reordered = beatmap_level.replacen(difficulty, "", 1).replacen(
+            "[General]",
+            &format!("{}[General]", difficulty),
+            1,
+        );
+
+        assert!(reordered.find("[Difficulty]") < reordered.find("[General]"));
+        assert!(BeatmapLevel::parse(&reordered)
+            .unwrap()
+            .diff(&BeatmapLevel::parse(&beatmap_level).unwrap())
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_matches_section_by_section() {
+        let beatmap_level = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+        let sections: HashMap<&str, &str> = split_sections(&beatmap_level).collect();
+        let expected = BeatmapLevel {
+            general: sections["General"].parse().unwrap(),
+            editor: sections["Editor"].parse().unwrap(),
+            metadata: sections["Metadata"].parse().unwrap(),
+            difficulty: sections["Difficulty"].parse().unwrap(),
+            events: sections["Events"].parse().unwrap(),
+            storyboard: sections["Events"].parse().unwrap(),
+            timing_points: sections["TimingPoints"].parse().unwrap(),
+            colours: sections["Colours"].parse().unwrap(),
+            hit_objects: sections["HitObjects"].parse().unwrap(),
+            unknown_sections: Vec::new(),
+            key_styles: KeyStyles::default(),
+        };
+        let parsed = BeatmapLevel::parse(&beatmap_level).unwrap();
+
+        assert!(parsed.diff(&expected).is_empty());
+        assert_eq!(parsed.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn round_trip_unknown_sections() {
+        let mut beatmap_level = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+        beatmap_level.push_str("\n[CustomStuff]\nKey: Value\n1,2,3\n");
+
+        let beatmap_level = BeatmapLevel::parse(&beatmap_level).unwrap();
+        let custom_section = (
+            String::from("CustomStuff"),
+            String::from("Key: Value\n1,2,3"),
+        );
+
+        assert_eq!(beatmap_level.unknown_sections, vec![custom_section.clone()]);
+        assert!(beatmap_level.hit_objects.last().unwrap().time > 0);
+
+        let serialized = beatmap_level.to_string();
+
+        assert!(serialized.ends_with("\n[CustomStuff]\nKey: Value\n1,2,3\n"));
+        assert_eq!(
+            BeatmapLevel::parse(&serialized).unwrap().unknown_sections,
+            vec![custom_section]
+        );
+    }
+
+    #[test]
+    fn round_trip_crlf_unknown_sections() {
+        let mut beatmap_level = fs::read_to_string(EDITOR_BEATMAP_LEVEL_PATH).unwrap();
+        beatmap_level.push_str("\r\n[CustomStuff]\r\nKey: Value\r\n1,2,3\r\n");
+
+        let beatmap_level = BeatmapLevel::parse(&beatmap_level).unwrap();
+        let serialized = beatmap_level.to_editor_string();
+
+        assert_eq!(beatmap_level.unknown_sections[0].1, "Key: Value\n1,2,3");
+        assert!(!serialized.contains("\r\r"));
+        assert!(serialized.ends_with("\r\n[CustomStuff]\r\nKey: Value\r\n1,2,3\r\n"));
+    }
+
+    #[test]
+    fn single_bpm() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let (min, max) = beatmap_level.bpm_range().unwrap();
+
+        assert!((min - 168.).abs() < 0.001);
+        assert!((max - 168.).abs() < 0.001);
+        assert!((beatmap_level.dominant_bpm().unwrap() - 168.).abs() < 0.001);
+    }
+
+    #[test]
+    fn multiple_bpm() {
+        let mut beatmap_level = BeatmapLevel::new();
+
+        assert_eq!(beatmap_level.bpm_range(), None);
+        assert_eq!(beatmap_level.dominant_bpm(), None);
+
+        beatmap_level.timing_points = "500,500,4,1,0,100,1,0
+5000,-50,4,1,0,100,0,0
+10000,300,4,1,0,100,1,0
+12000,400,4,1,0,100,1,0
+40000,300,4,1,0,100,1,0"
+            .parse()
+            .unwrap();
+        beatmap_level.hit_objects = "256,192,30000,1,0,0:0:0:0:".parse().unwrap();
+
+        assert_eq!(beatmap_level.bpm_range(), Some((120., 200.)));
+        assert_eq!(beatmap_level.dominant_bpm(), Some(150.));
+    }
+
+    #[test]
+    fn total_slider_distance() {
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.hit_objects = "256,192,11000,21,2,0:0:0:0:
+100,100,12600,6,1,B|200:200|250:200|250:200|300:150,2,310.123,2|1|2,0:0|0:0|0:2,0:0:0:0:
+100,100,13600,2,1,L|200:100,1,100,2|0,0:0|0:0,0:0:0:0:"
+            .parse()
+            .unwrap();
+
+        assert_eq!(beatmap_level.total_slider_distance(), 720.246);
+    }
+
+    #[test]
+    fn objects_with_timing() {
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.timing_points = "11100,500,4,0,0,100,1,0
+11150,-50,4,0,0,100,0,0
+12000,400,4,0,0,100,1,0"
+            .parse()
+            .unwrap();
+        beatmap_level.hit_objects = "256,192,11000,21,2,0:0:0:0:
+256,192,11200,8,12,12000,3:0:0:80:
+100,100,12600,6,1,B|200:200|250:200|250:200|300:150,2,310.123,2|1|2,0:0|0:0|0:2,0:0:0:0:"
+            .parse()
+            .unwrap();
+
+        let pairs: Vec<(i32, i32)> = beatmap_level
+            .objects_with_timing()
+            .map(|(hit_object, timing_point)| (hit_object.time, timing_point.time))
+            .collect();
+
+        assert_eq!(pairs, vec![(11200, 11100), (12600, 12000)]);
+    }
+
+    #[test]
+    fn open_difficulty() {
+        let dir = env::temp_dir().join(format!("osu-beatmap-parser-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let beatmap_level = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+        for version in ["Normal", "Insane"] {
+            fs::write(
+                dir.join(format!("{}.osu", version)),
+                beatmap_level.replace("Version:Crier's Hyper", &format!("Version:{}", version)),
+            )
+            .unwrap();
+        }
+        fs::write(dir.join("notes.txt"), "Version:Other").unwrap();
+
+        let insane = BeatmapLevel::open_difficulty(&dir, "Insane");
+        let missing = BeatmapLevel::open_difficulty(&dir, "Other");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(insane.unwrap().metadata.version, "Insane");
+        assert_eq!(
+            missing.unwrap_err().to_string(),
+            "No difficulty named 'Other' was found in the beatmap directory"
+        );
+    }
+
+    #[test]
+    fn add_in_time_order() {
+        let mut beatmap_level = BeatmapLevel::new();
+
+        for time in [3000, 1000, 2000, 1000] {
+            beatmap_level.add_hit_object(HitObject {
+                time,
+                x: beatmap_level.hit_objects.len() as i32,
+                ..Default::default()
+            });
+            beatmap_level.add_timing_point(TimingPoint {
+                time,
+                ..Default::default()
+            });
+        }
+
+        assert_eq!(
+            beatmap_level
+                .hit_objects
+                .iter()
+                .map(|x| (x.time, x.x))
+                .collect::<Vec<_>>(),
+            vec![(1000, 1), (1000, 3), (2000, 2), (3000, 0)]
+        );
+        assert!(beatmap_level
+            .timing_points
+            .windows(2)
+            .all(|x| x[0].time <= x[1].time));
+    }
+
+    #[test]
+    fn normalize_combos() {
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.hit_objects = "256,192,11000,1,2,0:0:0:0:
+256,192,11200,8,12,12000,3:0:0:80:
+256,192,12200,1,2,0:0:0:0:
+256,192,12400,1,2,0:0:0:0:"
+            .parse()
+            .unwrap();
+
+        beatmap_level.normalize_combos();
+
+        let new_combos: Vec<bool> = beatmap_level
+            .hit_objects
+            .iter()
+            .map(|x| x.new_combo)
+            .collect();
+
+        assert_eq!(new_combos, vec![true, false, true, false]);
+
+        beatmap_level.hit_objects[0].combo_skip = 2;
+        beatmap_level.normalize_combos();
+
+        assert!(beatmap_level.hit_objects[0].new_combo);
+        assert_eq!(beatmap_level.hit_objects[0].combo_skip, 0);
+    }
+
+    #[test]
+    fn repair_initial_timing() {
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.hit_objects = "256,192,1500,1,0,0:0:0:0:".parse().unwrap();
+        beatmap_level.timing_points = "1000,-50,4,2,1,60,0,0\n3000,400,4,2,1,60,1,0"
+            .parse()
+            .unwrap();
+
+        assert!(!beatmap_level.has_valid_initial_timing());
+
+        beatmap_level.repair_initial_timing(120.);
+
+        assert!(beatmap_level.has_valid_initial_timing());
+        assert_eq!(beatmap_level.timing_points.len(), 3);
+        assert_eq!(
+            beatmap_level.timing_points[0].to_string(),
+            "1000,500,4,2,1,60,1,0"
+        );
+        assert_eq!(beatmap_level.bpm_range(), Some((120., 150.)));
+
+        beatmap_level.repair_initial_timing(180.);
+        assert_eq!(beatmap_level.timing_points.len(), 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn repair_initial_timing_invalid_bpm() {
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.timing_points = "1000,-50,4,2,1,60,0,0".parse().unwrap();
+
+        beatmap_level.repair_initial_timing(0.);
+    }
+
+    #[test]
+    fn combo_numbers() {
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.hit_objects = "256,192,11000,5,2,0:0:0:0:
+256,192,11100,1,2,0:0:0:0:
+256,192,11200,1,2,0:0:0:0:
+256,192,11300,5,2,0:0:0:0:
+256,192,11400,1,2,0:0:0:0:
+256,192,11500,8,12,12000,3:0:0:80:
+256,192,12200,1,2,0:0:0:0:"
+            .parse()
+            .unwrap();
+
+        assert_eq!(beatmap_level.combo_numbers(), vec![1, 2, 3, 1, 2, 2, 1]);
+    }
+
+    #[test]
+    fn normalize_first_combo() {
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.hit_objects = "256,192,11000,21,2,0:0:0:0:
+256,192,11200,8,12,12000,3:0:0:80:
+256,192,12200,1,2,0:0:0:0:
+256,192,12400,1,2,0:0:0:0:"
+            .parse()
+            .unwrap();
+
+        assert_eq!(beatmap_level.hit_objects[0].combo_skip, 1);
+        assert!((0..5).map(|x| beatmap_level.starts_new_combo(x)).eq([
+            Some(true),
+            Some(false),
+            Some(true),
+            Some(false),
+            None
+        ]));
+
+        beatmap_level.normalize_first_combo();
+
+        assert!(beatmap_level.hit_objects[0].new_combo);
+        assert_eq!(beatmap_level.hit_objects[0].combo_skip, 0);
+        assert_eq!(
+            beatmap_level.hit_objects[0].to_string(),
+            "256,192,11000,5,2,0:0:0:0:"
+        );
+    }
+
+    #[test]
+    fn hit_objects_between() {
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.hit_objects = "256,192,11000,21,2,0:0:0:0:
+256,192,11200,8,12,12000,3:0:0:80:
+100,100,12600,6,1,B|200:200|300:150,1,310.123,2|2,0:0|0:0,0:0:0:0:"
+            .parse()
+            .unwrap();
+
+        assert!(beatmap_level
+            .hit_objects_between(11001, 12599)
+            .map(|x| x.time)
+            .eq([11200]));
+        assert_eq!(beatmap_level.hit_objects_between(11000, 12600).count(), 3);
+        assert_eq!(beatmap_level.hit_objects_between(12601, 13000).count(), 0);
+        assert_eq!(beatmap_level.hit_objects_between(12000, 11000).count(), 0);
+
+        beatmap_level
+            .hit_objects_between_mut(11200, 11200)
+            .for_each(|x| x.x = 0);
+
+        assert!(beatmap_level
+            .hit_objects
+            .iter()
+            .map(|x| x.x)
+            .eq([256, 0, 100]));
+    }
+
+    #[test]
+    fn clear_hitsounds() {
+        let mut beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+
+        assert!(beatmap_level
+            .hit_objects
+            .iter()
+            .any(|x| x.hit_sound != HitSoundFlag::default()));
+
+        beatmap_level.clear_hitsounds();
+
+        for hit_object in beatmap_level.hit_objects.iter() {
+            assert_eq!(hit_object.hit_sound, HitSoundFlag::default());
+            assert_eq!(hit_object.hit_sample, HitSample::default());
+
+            if let HitObjectType::Slider(params) = &hit_object.object_params {
+                assert!(params.edge_sounds.sounds.iter().all(|x| *x == 0));
+                assert!(params.edge_sounds.sets.iter().all(|x| *x == (0, 0)));
+            }
+        }
+    }
+
+    #[test]
+    fn kiai_sections() {
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.timing_points = "1000,500,4,1,0,60,1,0
+2000,-100,4,1,0,60,0,1
+2500,-50,4,1,0,60,0,1
+4000,-100,4,1,0,60,0,0
+5000,-100,4,1,0,60,0,0"
+            .parse()
+            .unwrap();
+
+        assert!(beatmap_level.timing_points[1].is_kiai());
+        assert!(!beatmap_level.timing_points[3].is_kiai());
+        assert_eq!(beatmap_level.kiai_sections(), vec![(2000, 4000)]);
+
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+
+        assert_eq!(
+            beatmap_level.kiai_sections(),
+            vec![(34335, 34692), (81120, 81210), (126835, 149692)]
+        );
+    }
+
+    #[test]
+    fn copy_timing_from() {
+        let source = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let mut beatmap_level = BeatmapLevel::new();
+
+        beatmap_level.copy_timing_from(&source, false);
+
+        assert_eq!(beatmap_level.timing_points, source.timing_points);
+        assert_ne!(
+            beatmap_level.difficulty.slider_multiplier,
+            source.difficulty.slider_multiplier
+        );
+
+        beatmap_level.copy_timing_from(&source, true);
+
+        assert_eq!(
+            beatmap_level.difficulty.slider_multiplier,
+            source.difficulty.slider_multiplier
+        );
+    }
+
+    #[test]
+    fn parse_streaming() {
+        let s = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+        let mut parsed = Vec::new();
+
+        let beatmap_level = BeatmapLevel::parse_streaming(&s, |x| parsed.push(x)).unwrap();
+
+        assert_eq!(parsed, SectionId::ALL);
+        assert_eq!(
+            beatmap_level.to_string(),
+            BeatmapLevel::parse(&s).unwrap().to_string()
+        );
+
+        let mut parsed = Vec::new();
+        let truncated = &s[..s.find("[HitObjects]").unwrap()];
+
+        assert!(BeatmapLevel::parse_streaming(truncated, |x| parsed.push(x)).is_err());
+        assert_eq!(parsed, SectionId::ALL[..7]);
+    }
+
+    #[test]
+    fn parse_streaming_missing_colours() {
+        let s = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+        let s = format!(
+            "{}{}",
+            &s[..s.find("[Colours]").unwrap()],
+            &s[s.find("[HitObjects]").unwrap()..]
+        );
+        let mut parsed = Vec::new();
+
+        let beatmap_level = BeatmapLevel::parse_streaming(&s, |x| parsed.push(x)).unwrap();
+
+        assert!(beatmap_level.colours.is_empty());
+        assert!(!parsed.contains(&SectionId::Colours));
+        assert_eq!(parsed.len(), SectionId::ALL.len() - 1);
+    }
+
+    #[test]
+    fn parse_format_version() {
+        assert_eq!(
+            BeatmapLevel::parse_format_version("osu file format v14\n"),
+            14
+        );
+        assert_eq!(
+            BeatmapLevel::parse_format_version("osu file format v9\r\n"),
+            9
+        );
+        assert_eq!(
+            BeatmapLevel::parse_format_version("\u{feff}Osu File Format V7 \n"),
+            7
+        );
+        assert_eq!(
+            BeatmapLevel::parse_format_version("osu file format vX\n"),
+            14
+        );
+        assert_eq!(BeatmapLevel::parse_format_version("[General]\nMode: 0"), 14);
+        assert_eq!(BeatmapLevel::parse_format_version(""), 14);
+    }
+
+    #[test]
+    fn parse_metadata_only() {
+        let s = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+        let beatmap_level = BeatmapLevel::parse(&s).unwrap();
+
+        assert_eq!(
+            BeatmapLevel::parse_metadata_only(&s).unwrap(),
+            beatmap_level.metadata
+        );
+        assert!(matches!(
+            BeatmapLevel::parse_metadata_only("[General]\nMode: 0"),
+            Err(BeatmapParseError::SectionNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_metadata_only_repeated_section() {
+        let mut s = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+        s.push_str("\n[Metadata]\nTitle:Override\n");
+        let beatmap_level = BeatmapLevel::parse(&s).unwrap();
+        let metadata = BeatmapLevel::parse_metadata_only(&s).unwrap();
+
+        assert_eq!(metadata.title, "Override");
+        assert_eq!(metadata, beatmap_level.metadata);
+    }
+
+    #[test]
+    fn object_spacings() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let spacings = beatmap_level.object_spacings();
+
+        assert_eq!(spacings.len(), beatmap_level.hit_objects.len() - 1);
+        assert!(spacings.iter().all(|(distance, _)| *distance >= 0.));
+
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.hit_objects =
+            "0,0,1000,1,0,0:0:0:0:\n30,40,1000,1,0,0:0:0:0:\n30,40,1500,1,0,0:0:0:0:"
+                .parse()
+                .unwrap();
+
+        assert_eq!(beatmap_level.object_spacings(), vec![(50., 0), (0., 500)]);
+        assert_eq!(beatmap_level.object_velocities(), vec![None, Some(0.)]);
+
+        // The first slider ends at the end of its path, the second one on its head
+        beatmap_level.hit_objects = "0,0,1000,2,0,L|100:0,1,50
+50,40,2000,2,0,B|50:100|100:100,2,100
+80,80,3000,1,0,0:0:0:0:"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            beatmap_level.object_spacings(),
+            vec![(40., 1000), (50., 1000)]
+        );
+        assert_eq!(
+            beatmap_level.object_velocities(),
+            vec![Some(0.04), Some(0.05)]
+        );
+    }
+
+    #[test]
+    fn sample_state_at() {
+        let mut beatmap_level = BeatmapLevel::new();
+        assert_eq!(
+            beatmap_level.sample_state_at(0),
+            (SampleSet::Default, 0, 100)
+        );
+
+        beatmap_level.timing_points = "1000,500,4,1,0,80,1,0
+2000,-100,4,2,3,50,0,0"
+            .parse()
+            .unwrap();
+
+        assert_eq!(beatmap_level.sample_state_at(0), (SampleSet::Normal, 0, 80));
+        assert_eq!(
+            beatmap_level.sample_state_at(1999),
+            (SampleSet::Normal, 0, 80)
+        );
+        assert_eq!(
+            beatmap_level.sample_state_at(2000),
+            (SampleSet::Soft, 3, 50)
+        );
+        assert_eq!(
+            beatmap_level.sample_state_at(5000),
+            (SampleSet::Soft, 3, 50)
+        );
+    }
+
+    #[test]
+    fn assert_mode() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+
+        assert_eq!(beatmap_level.assert_mode(Gamemode::STD), Ok(()));
+        assert_eq!(
+            beatmap_level.assert_mode(Gamemode::MANIA),
+            Err(BeatmapParseError::UnexpectedGamemode {
+                expected: Gamemode::MANIA,
+                found: Gamemode::STD,
+            })
+        );
+    }
+
+    #[test]
+    fn positions() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let positions = beatmap_level.positions(false);
+
+        assert_eq!(positions.len(), beatmap_level.hit_objects.len());
+        let first = &beatmap_level.hit_objects[0];
+        assert_eq!(positions[0], (first.x, first.y, first.time));
+
+        let path_points: usize = beatmap_level
+            .hit_objects
+            .iter()
+            .filter_map(|x| x.slider_path(SLIDER_PATH_SPACING))
+            .map(|path| path.len() - 1)
+            .sum();
+        assert_eq!(
+            beatmap_level.positions(true).len(),
+            beatmap_level.hit_objects.len() + path_points
+        );
+
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.hit_objects = "100,100,1000,2,0,L|200:100,1,50
+100,100,2000,2,0,B|100:200|200:200|200:200|200:100,1,250"
+            .parse()
+            .unwrap();
+        let positions = beatmap_level.positions(true);
+
+        assert_eq!(
+            positions[..6],
+            [
+                (100, 100, 1000),
+                (110, 100, 1000),
+                (120, 100, 1000),
+                (130, 100, 1000),
+                (140, 100, 1000),
+                (150, 100, 1000)
+            ]
+        );
+        // The control point of the curve isn't on the path
+        assert!(!positions.contains(&(100, 200, 2000)));
+        // The curve cuts the corner, the slider ends on the straight segment after the red anchor
+        let (x, y, _) = positions[positions.len() - 1];
+        assert!(x == 200 && (100..200).contains(&y));
+    }
+
+    #[test]
+    fn simultaneous_objects() {
+        let mut beatmap_level = BeatmapLevel::new();
+
+        assert!(beatmap_level.simultaneous_objects().is_empty());
+
+        beatmap_level.hit_objects = "64,192,1000,1,0,0:0:0:0:
+192,192,1000,1,0,0:0:0:0:
+320,192,1500,1,0,0:0:0:0:
+64,192,2000,1,0,0:0:0:0:
+192,192,2000,1,0,0:0:0:0:
+448,192,2000,1,0,0:0:0:0:"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            beatmap_level.simultaneous_objects(),
+            vec![vec![0, 1], vec![3, 4, 5]]
+        );
+    }
+
+    #[test]
+    fn parse_lenient_decimals() {
+        let s = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH)
+            .unwrap()
+            .replace("SliderMultiplier:1.5", "SliderMultiplier:1,5");
+
+        assert!(BeatmapLevel::parse(&s).is_err());
+
+        let beatmap_level =
+            BeatmapLevel::parse_with(&s, &ParseOptions::new().lenient(true)).unwrap();
+
+        assert_eq!(beatmap_level.difficulty.slider_multiplier, 1.5);
+    }
+
+    #[test]
+    fn gameplay_eq() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let mut other = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        other.metadata.version = String::from("Other");
+        other.metadata.tags.clear();
+        other.editor.grid_size = 4;
+        other.colours.combos[0] = None;
+
+        assert!(beatmap_level.gameplay_eq(&other));
+
+        other.difficulty.approach_rate += 1.;
+
+        assert!(!beatmap_level.gameplay_eq(&other));
+    }
+
+    #[test]
+    fn timing_points_by_kind() {
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.timing_points = "10000,333.33,4,0,0,100,1,1
+12000,-25,4,3,0,100,0,1"
+            .parse()
+            .unwrap();
+
+        assert!(beatmap_level
+            .uninherited_points()
+            .map(|x| x.time)
+            .eq([10000]));
+        assert!(beatmap_level.inherited_points().map(|x| x.time).eq([12000]));
+    }
+
+    #[test]
+    fn content_hash() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let mut other = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        other.editor.grid_size = 4;
+        other.metadata.version = String::from("Other");
+
+        assert_eq!(beatmap_level.content_hash(), other.content_hash());
+
+        if let HitObjectType::Slider(params) = &mut other.hit_objects[0].object_params {
+            params.length += 1.;
+        }
+
+        assert_ne!(beatmap_level.content_hash(), other.content_hash());
+    }
+
+    #[test]
+    fn background_filename() {
+        let mut beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let events_count = beatmap_level.events.len();
+
+        assert_eq!(beatmap_level.background_filename(), Some("bg.jpg"));
+
+        beatmap_level.set_background("other.png");
+
+        assert_eq!(beatmap_level.background_filename(), Some("other.png"));
+        assert_eq!(beatmap_level.events.len(), events_count);
+    }
+
+    #[test]
+    fn set_audio_filename() {
+        let mut beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+
+        beatmap_level.set_audio_filename("renamed.ogg");
+
+        assert_eq!(beatmap_level.general.audio_filename, "renamed.ogg");
+        assert!(beatmap_level
+            .to_string()
+            .contains("AudioFilename: renamed.ogg\n"));
+    }
+
+    #[test]
+    fn breaks() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+
+        assert_eq!(
+            beatmap_level.breaks().collect::<Vec<_>>(),
+            vec![(104177, 114656)]
+        );
+        assert_eq!(BeatmapLevel::new().breaks().count(), 0);
+    }
+
+    #[test]
+    fn video_filename() {
+        let mut beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+
+        assert_eq!(beatmap_level.video_filename(), None);
+        assert_eq!(beatmap_level.main_visual_filename(), Some("bg.jpg"));
+
+        beatmap_level
+            .events
+            .push("1,0,\"video.mp4\",0,0".parse().unwrap());
+
+        assert_eq!(beatmap_level.video_filename(), Some("video.mp4"));
+        assert_eq!(beatmap_level.background_filename(), Some("bg.jpg"));
+        assert_eq!(beatmap_level.main_visual_filename(), Some("video.mp4"));
+    }
+
+    #[test]
+    fn set_missing_background() {
+        let mut beatmap_level = BeatmapLevel::new();
+
+        assert_eq!(beatmap_level.background_filename(), None);
+
+        beatmap_level.set_background("bg.jpg");
+
+        assert_eq!(beatmap_level.background_filename(), Some("bg.jpg"));
+        assert_eq!(beatmap_level.events[0].start_time, 0);
+        assert_eq!(beatmap_level.events[0].to_string(), "0,0,\"bg.jpg\"");
+    }
+
+    #[test]
+    fn round_trip_storyboard() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let serialized = beatmap_level.to_string();
+        let reparsed = BeatmapLevel::parse(&serialized).unwrap();
+
+        assert_eq!(beatmap_level.storyboard.foreground.len(), 720);
+        assert_eq!(reparsed.storyboard, beatmap_level.storyboard);
+        assert!(serialized.contains(
+            "//Storyboard Layer 3 (Foreground)\nSprite,Foreground,Centre,\"SB BG/highlight.png\",320,240\n C,0,0,,142,142,255\n"
+        ));
+        assert!(!serialized.contains("//Storyboard Layer 4 (Overlay)"));
+        assert!(serialized.contains("//Storyboard Sound Samples\n\n[TimingPoints]"));
+    }
+
+    #[test]
+    fn serialize_stable_layout() {
+        let stable = fs::read_to_string(STABLE_BEATMAP_LEVEL_PATH).unwrap();
+        let beatmap_level = BeatmapLevel::parse(&stable).unwrap();
+        // Only the fields with their default value aren't written by default
+        let without_defaults = [
+            "AudioLeadIn: 0",
+            "SampleSet: Normal",
+            "Mode: 0",
+            "LetterboxInBreaks: 0",
+        ]
+        .iter()
+        .fold(stable.clone(), |s, x| s.replace(&format!("{}\r\n", x), ""));
+
+        assert_eq!(
+            beatmap_level.to_string().replace('\n', "\r\n"),
+            without_defaults
+        );
+        assert_eq!(beatmap_level.to_editor_string(), stable);
+    }
+
+    #[test]
+    fn serialize_editor_round_trip() {
+        let editor = fs::read_to_string(EDITOR_BEATMAP_LEVEL_PATH).unwrap();
+        let beatmap_level = BeatmapLevel::parse(&editor).unwrap();
+
+        assert_eq!(beatmap_level.to_editor_string(), editor);
+        assert!(!beatmap_level.to_string().contains("Mode: 0"));
+        assert!(!beatmap_level.to_string().contains('\r'));
+    }
+
+    #[test]
+    fn round_trip_key_styles() {
+        let s = fs::read_to_string(STABLE_BEATMAP_LEVEL_PATH)
+            .unwrap()
+            .replace("AudioFilename: audio.mp3", "audiofilename:audio.mp3");
+        let beatmap_level =
+            BeatmapLevel::parse_with(&s, &ParseOptions::new().lenient(true)).unwrap();
+
+        assert_eq!(beatmap_level.general.audio_filename, "audio.mp3");
+        let serialized = beatmap_level.to_string();
+        assert!(serialized.contains("[General]\naudiofilename:audio.mp3\nPreviewTime: 138765\n"));
+        assert!(!serialized.contains("AudioFilename"));
+
+        assert_eq!(BeatmapLevel::parse(&s).unwrap().general.audio_filename, "");
+    }
+
+    #[test]
+    fn key_styles_strict_mode() {
+        let s = fs::read_to_string(STABLE_BEATMAP_LEVEL_PATH)
+            .unwrap()
+            .replace("PreviewTime: 138765", "previewtime: 5000");
+        let mut beatmap_level = BeatmapLevel::parse(&s).unwrap();
+
+        assert_eq!(beatmap_level.general.preview_time, 0);
+
+        beatmap_level.general.preview_time = 1234;
+        let serialized = beatmap_level.to_string();
+        assert!(serialized.contains("PreviewTime: 1234\n"));
+        assert!(!serialized.contains("previewtime"));
+        assert_eq!(
+            BeatmapLevel::parse(&serialized)
+                .unwrap()
+                .general
+                .preview_time,
+            1234
+        );
+    }
+
+    #[test]
+    fn key_style_of_exact_key() {
+        let s = fs::read_to_string(STABLE_BEATMAP_LEVEL_PATH)
+            .unwrap()
+            .replace(
+                "AudioFilename: audio.mp3",
+                "AudioFilename: a.mp3\naudiofilename:b.mp3",
+            );
+        let beatmap_level =
+            BeatmapLevel::parse_with(&s, &ParseOptions::new().lenient(true)).unwrap();
+
+        assert_eq!(beatmap_level.general.audio_filename, "a.mp3");
+        assert!(beatmap_level
+            .to_string()
+            .contains("[General]\nAudioFilename: a.mp3\n"));
+    }
+
+    #[test]
+    fn migrate_legacy_editor_bookmarks() {
+        let s = fs::read_to_string(STABLE_BEATMAP_LEVEL_PATH)
+            .unwrap()
+            .replace("Bookmarks: 93145,127635,150629,228833", "")
+            .replace("[General]", "[General]\nEditorBookmarks: 1000,2000");
+        let beatmap_level = BeatmapLevel::parse(&s).unwrap();
+
+        assert_eq!(beatmap_level.editor.bookmarks, vec![1000, 2000]);
+        let serialized = beatmap_level.to_string();
+        assert!(serialized.contains("[Editor]\nBookmarks: 1000,2000\n"));
+        assert!(!serialized.contains("EditorBookmarks"));
+    }
+
+    #[test]
+    fn serialize_omits_empty_sections() {
+        let mut beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        beatmap_level.colours = Colours::default();
+
+        let serialized = beatmap_level.to_string();
+        assert!(!serialized.contains("[Colours]"));
+        assert!(serialized.contains("[TimingPoints]"));
+        assert_eq!(
+            BeatmapLevel::parse(&serialized).unwrap().to_string(),
+            serialized
+        );
+
+        beatmap_level.timing_points = CommaListOf::default();
+        let options = SerializeOptions::new().omit_empty_timing_points(true);
+        let serialized = beatmap_level.to_string_with(&options);
+        assert!(!serialized.contains("[TimingPoints]"));
+        assert!(BeatmapLevel::parse(&serialized)
+            .unwrap()
+            .timing_points
+            .is_empty());
+    }
+
+    #[test]
+    fn serialize_sections() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let serialized =
+            beatmap_level.serialize_sections(&[SectionId::General, SectionId::Metadata]);
+
+        assert_eq!(
+            serialized,
+            format!(
+                "[General]\n{}\n[Metadata]\n{}",
+                beatmap_level.general.to_string(),
+                beatmap_level.metadata.to_string()
+            )
+        );
+        assert!(serialized.starts_with("[General]\nAudioFilename: marb.mp3\n"));
+        assert!(!serialized.contains("[HitObjects]"));
+    }
+
+    #[test]
+    fn try_from_str() -> Result<(), BeatmapParseError> {
+        let sample = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+        let beatmap_level: BeatmapLevel = sample.as_str().try_into()?;
+
+        assert_eq!(beatmap_level.first_object_time(), Some(1121));
+        Ok(())
+    }
+
     #[test]
     fn parse_save_beatmap_level() {
         let mut file = File::open(TEST_BEATMAP_LEVEL_PATH).unwrap();
@@ -0,0 +1,93 @@
+/// Options controlling how a beatmap is serialized.
+/// The default options produce the output expected by current osu! versions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    /// Write the deprecated `[General]` fields (`AudioHash`, `StoryFireInFront` and
+    /// `AlwaysShowPlayfield`) when they are set, for an exact round-trip of old beatmaps.
+    pub emit_deprecated: bool,
+    /// Write the fields always written by the osu! editor even when they have their default
+    /// value, such as `Mode: 0` or an empty `Source:`.
+    pub editor_fields: bool,
+    /// Use `\r\n` line endings, as the osu! editor does.
+    pub crlf: bool,
+    /// Don't write the `[TimingPoints]` section when there are no timing points.
+    /// The `[Colours]` section is always omitted when empty, as osu! does.
+    pub omit_empty_timing_points: bool,
+    /// Write hit circles with a default hit sample without it (`256,192,1000,1,2,`), as some
+    /// versions of osu! did, instead of ending them with `0:0:0:0:`.
+    pub omit_default_hit_sample: bool,
+}
+
+impl SerializeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options producing the same output as the osu! editor.
+    pub fn editor() -> Self {
+        Self::new().editor_fields(true).crlf(true)
+    }
+
+    pub fn editor_fields(mut self, editor_fields: bool) -> Self {
+        self.editor_fields = editor_fields;
+        self
+    }
+
+    pub fn crlf(mut self, crlf: bool) -> Self {
+        self.crlf = crlf;
+        self
+    }
+
+    pub fn emit_deprecated(mut self, emit_deprecated: bool) -> Self {
+        self.emit_deprecated = emit_deprecated;
+        self
+    }
+
+    pub fn omit_empty_timing_points(mut self, omit_empty_timing_points: bool) -> Self {
+        self.omit_empty_timing_points = omit_empty_timing_points;
+        self
+    }
+
+    pub fn omit_default_hit_sample(mut self, omit_default_hit_sample: bool) -> Self {
+        self.omit_default_hit_sample = omit_default_hit_sample;
+        self
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Options controlling how a beatmap is parsed.
+/// The default options are strict and only accept the format written by osu!.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Accept malformed values written by some tools in the `[General]` and `[Difficulty]`
+    /// sections:
+    /// - a comma as the decimal separator of decimal fields (`SliderMultiplier:1,5`),
+    /// - decimals in integer fields, which are truncated (`PreviewTime: 126478.0`).
+    ///
+    /// Keys written with another casing (`audiofilename: a.mp3`) are also accepted in all the
+    /// `key:value` sections when a field isn't written with its exact key.
+    ///
+    /// Trailing `//` comments of hit object lines (`256,192,11000,5,0 // stack`) are also
+    /// removed.
+    pub lenient: bool,
+    /// Keep the trailing comments removed from hit object lines in lenient mode, to write them
+    /// back when serializing the beatmap.
+    pub keep_comments: bool,
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    pub fn keep_comments(mut self, keep_comments: bool) -> Self {
+        self.keep_comments = keep_comments;
+        self
+    }
+}
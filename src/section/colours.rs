@@ -1,9 +1,10 @@
 use crate::error::BeatmapParseError;
-use crate::error::BeatmapParseError::InvalidFormat;
+use crate::error::BeatmapParseError::{InvalidComboIndex, InvalidFormat};
 use crate::section::{CommaListElement, Section};
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ColourType {
     Combo(u8),
     SliderTrackOverride,
@@ -45,11 +46,15 @@ impl ToString for ColourType {
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Representation of the red, green, and blue components of the colours.
-#[derive(Debug, Default, PartialEq, Eq)]
+///
+/// The osu! editor keeps an alpha component when one is written after the blue one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Rgb {
     pub red: u8,
     pub green: u8,
     pub blue: u8,
+    /// Fourth component, only present when the beatmap writes one.
+    pub alpha: Option<u8>,
 }
 
 impl FromStr for Rgb {
@@ -62,19 +67,28 @@ impl FromStr for Rgb {
             red: u8::from_str(s[0]).map_err(|_| InvalidFormat {
                 field: "red".to_string(),
             })?,
-            green: u8::from_str(s[1]).map_err(|_| InvalidFormat {
+            green: u8::from_str(s.get(1).unwrap_or(&"")).map_err(|_| InvalidFormat {
                 field: "green".to_string(),
             })?,
-            blue: u8::from_str(s[2]).map_err(|_| InvalidFormat {
+            blue: u8::from_str(s.get(2).unwrap_or(&"")).map_err(|_| InvalidFormat {
                 field: "blue".to_string(),
             })?,
+            alpha: match s.get(3) {
+                Some(alpha) => Some(u8::from_str(alpha).map_err(|_| InvalidFormat {
+                    field: "alpha".to_string(),
+                })?),
+                None => None,
+            },
         })
     }
 }
 
 impl ToString for Rgb {
     fn to_string(&self) -> String {
-        format!("{},{},{}", self.red, self.green, self.blue)
+        match self.alpha {
+            Some(alpha) => format!("{},{},{},{}", self.red, self.green, self.blue, alpha),
+            None => format!("{},{},{}", self.red, self.green, self.blue),
+        }
     }
 }
 
@@ -90,22 +104,35 @@ impl FromStr for Colour {
     type Err = BeatmapParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s: Vec<&str> = s.trim().split(":").map(|x| x.trim()).collect();
+        let line = s.trim();
+        let invalid_format = || InvalidFormat {
+            field: "colour".to_string(),
+        };
+        let invalid_combo_index = || InvalidComboIndex {
+            line: line.to_string(),
+        };
+
+        let (key, value) = line.split_once(':').ok_or_else(invalid_format)?;
+        let key = key.trim();
+
+        let colour_of = match ColourType::from_str(key) {
+            Ok(ColourType::Combo(x)) if !(1..=8).contains(&x) => Err(invalid_combo_index()),
+            Ok(x) => Ok(x),
+            Err(_) if key.starts_with("Combo") => Err(invalid_combo_index()),
+            Err(_) => Err(invalid_format()),
+        }?;
 
         Ok(Colour {
-            colour_of: ColourType::from_str(s[0]).map_err(|_| InvalidFormat {
-                field: "colour".to_string(),
-            })?,
-            colour: Rgb::from_str(s[1]).map_err(|_| InvalidFormat {
-                field: "colour".to_string(),
-            })?,
+            colour_of,
+            colour: Rgb::from_str(value).map_err(|_| invalid_format())?,
         })
     }
 }
 
-impl ToString for Colour {
-    fn to_string(&self) -> String {
-        format!(
+impl Display for Colour {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
             "{} : {}",
             self.colour_of.to_string(),
             self.colour.to_string()
@@ -118,7 +145,7 @@ impl CommaListElement for Colour {}
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Combo and skin colours.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq, Eq)]
 pub struct Colours {
     /// Additive combo colours
     pub combos: [Option<Colour>; 8],
@@ -126,6 +153,44 @@ pub struct Colours {
     pub slider_track_override: Option<Colour>,
     /// Slider border colour
     pub slider_border: Option<Colour>,
+    /// Order in which the colours were declared in the beatmap file, used to serialize them back
+    /// in the same order. Colours absent from it are serialized after, combos first.
+    pub order: Vec<ColourType>,
+}
+
+impl Colours {
+    fn get(&self, colour_type: &ColourType) -> Option<&Colour> {
+        match colour_type {
+            ColourType::Combo(x) => self
+                .combos
+                .get((*x as usize).wrapping_sub(1))
+                .and_then(|x| x.as_ref()),
+            ColourType::SliderTrackOverride => self.slider_track_override.as_ref(),
+            ColourType::SliderBorder => self.slider_border.as_ref(),
+        }
+    }
+
+    /// Whether no colour is defined, in which case osu! doesn't write the section.
+    pub fn is_empty(&self) -> bool {
+        self.combos.iter().all(|x| x.is_none())
+            && self.slider_track_override.is_none()
+            && self.slider_border.is_none()
+    }
+
+    /// Fill the empty combo slots among the first `n` ones with the `default` colour,
+    /// so at least `n` combo colours are defined. Existing combo colours are kept.
+    ///
+    /// `n` is capped to the 8 combo colours supported by osu!.
+    pub fn ensure_at_least(&mut self, n: usize, default: Rgb) {
+        for (i, combo) in self.combos.iter_mut().enumerate().take(n) {
+            if combo.is_none() {
+                *combo = Some(Colour {
+                    colour_of: ColourType::Combo(i as u8 + 1),
+                    colour: default,
+                });
+            }
+        }
+    }
 }
 
 impl FromStr for Colours {
@@ -135,8 +200,9 @@ impl FromStr for Colours {
         let mut colours = Colours::new();
         let s: Vec<&str> = s.trim().split("\n").map(|x| x.trim()).collect();
 
-        for x in s.iter() {
+        for x in s.iter().filter(|x| !x.is_empty()) {
             let colour = Colour::from_str(x)?;
+            colours.order.push(colour.colour_of);
 
             match colour.colour_of {
                 ColourType::Combo(x) => colours.combos[x as usize - 1] = Some(colour),
@@ -152,31 +218,24 @@ impl FromStr for Colours {
 impl ToString for Colours {
     fn to_string(&self) -> String {
         let mut buf = String::new();
-
-        for combo in &self.combos {
-            match combo {
-                Some(c) => {
-                    buf.push_str(&c.to_string());
-                    buf.push_str("\n");
-                }
-                None => (),
+        let mut written: Vec<&ColourType> = Vec::new();
+
+        let declared = self.order.iter().filter_map(|x| self.get(x));
+        let others = self
+            .combos
+            .iter()
+            .flatten()
+            .chain(self.slider_track_override.iter())
+            .chain(self.slider_border.iter());
+
+        for colour in declared.chain(others) {
+            if written.contains(&&colour.colour_of) {
+                continue;
             }
-        }
+            written.push(&colour.colour_of);
 
-        match &self.slider_track_override {
-            Some(s) => {
-                buf.push_str(&s.to_string());
-                buf.push_str("\n");
-            }
-            None => (),
-        }
-
-        match &self.slider_border {
-            Some(s) => {
-                buf.push_str(&s.to_string());
-                buf.push_str("\n");
-            }
-            None => (),
+            buf.push_str(&colour.to_string());
+            buf.push('\n');
         }
 
         buf
@@ -187,6 +246,7 @@ impl Section for Colours {}
 
 #[cfg(test)]
 mod tests {
+    use crate::error::BeatmapParseError;
     use crate::section::colours::{Colour, ColourType, Colours, Rgb};
     use crate::section::Section;
 
@@ -218,6 +278,15 @@ Combo2 : 202,202,202
         assert_eq!(colours.slider_border, None);
     }
 
+    #[test]
+    fn parse_colours_with_empty_lines() {
+        let colours = Colours::parse("Combo1 : 255,0,0\n\n   \nCombo2 : 202,202,202").unwrap();
+
+        assert!(colours.combos[0].is_some());
+        assert!(colours.combos[1].is_some());
+        assert!(Colours::parse("").unwrap().is_empty());
+    }
+
     #[test]
     fn serialize_colours() {
         let mut colours = Colours::new();
@@ -227,6 +296,7 @@ Combo2 : 202,202,202
                 red: 255,
                 green: 0,
                 blue: 0,
+                alpha: None,
             },
         };
         let colours_combo_two = Colour {
@@ -235,6 +305,7 @@ Combo2 : 202,202,202
                 red: 202,
                 green: 202,
                 blue: 202,
+                alpha: None,
             },
         };
         colours.combos[0] = Some(colours_combo_one);
@@ -243,6 +314,85 @@ Combo2 : 202,202,202
         assert_eq!(colours.serialize(), TEST_COLOURS);
     }
 
+    #[test]
+    fn reject_invalid_combo_index() {
+        for line in ["Combo : 1,2,3", "Combo0 : 1,2,3", "Combo9 : 1,2,3"] {
+            assert_eq!(
+                Colours::parse(&format!("{}{}\n", TEST_COLOURS, line)).unwrap_err(),
+                BeatmapParseError::InvalidComboIndex {
+                    line: line.to_string()
+                }
+            );
+        }
+        assert_eq!(
+            Colours::parse("Combo : 1,2,3").unwrap_err().to_string(),
+            "Invalid combo colour index in 'Combo : 1,2,3', expected Combo1 to Combo8"
+        );
+    }
+
+    #[test]
+    fn reject_malformed_colours() {
+        for line in ["Combo1", "SliderBorder", "Combo1 : 1,2", "Combo1 255,0,0"] {
+            assert_eq!(
+                Colours::parse(line).unwrap_err(),
+                BeatmapParseError::InvalidFormat {
+                    field: "colour".to_string()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn ensure_at_least_combos() {
+        let mut colours = Colours::parse(TEST_COLOURS).unwrap();
+        let default = Rgb {
+            red: 255,
+            green: 255,
+            blue: 255,
+            alpha: None,
+        };
+
+        colours.ensure_at_least(4, default);
+
+        assert_eq!(colours.combos.iter().flatten().count(), 4);
+        assert_eq!(colours.combos[0].as_ref().unwrap().colour.green, 0);
+        assert_eq!(colours.combos[4], None);
+        assert_eq!(
+            colours.serialize(),
+            format!(
+                "{}Combo3 : 255,255,255\nCombo4 : 255,255,255\n",
+                TEST_COLOURS
+            )
+        );
+    }
+
+    #[test]
+    fn round_trip_colours_order() {
+        let reversed_colours = "Combo2 : 202,202,202
+SliderBorder : 1,2,3
+Combo1 : 255,0,0
+";
+        let mut colours = Colours::parse(reversed_colours).unwrap();
+
+        assert_eq!(colours.combos[0].as_ref().unwrap().colour.red, 255);
+        assert_eq!(colours.serialize(), reversed_colours);
+
+        colours.combos[2] = Some(Colour {
+            colour_of: ColourType::Combo(3),
+            colour: Rgb {
+                red: 0,
+                green: 0,
+                blue: 0,
+                alpha: None,
+            },
+        });
+
+        assert_eq!(
+            colours.serialize(),
+            format!("{}Combo3 : 0,0,0\n", reversed_colours)
+        );
+    }
+
     mod colour_type {
         use crate::section::colours::ColourType;
         use core::str::FromStr;
@@ -298,6 +448,7 @@ Combo2 : 202,202,202
                 red: 255,
                 green: 202,
                 blue: 202,
+                alpha: None,
             };
 
             assert_eq!(rgb.to_string(), TEST_RGB)
@@ -1,9 +1,14 @@
 use crate::error::BeatmapParseError;
-use crate::section::{Section, SectionKeyValue};
+use crate::options::{ParseOptions, SerializeOptions};
+use crate::section::{KeyValueLines, Section, SectionKeyValue};
+use crate::types::difficulty::DiffMod;
+use crate::types::general::Gamemode;
+use crate::validation::ValidationWarning;
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 
 /// [Difficulty settings](https://osu.ppy.sh/wiki/en/Client/Beatmap_editor/Song_Setup#difficulty)
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq)]
 pub struct DifficultySection {
     /// HP setting (0–10)
     pub hp_drain_rate: f32,
@@ -20,51 +25,205 @@ pub struct DifficultySection {
     pub slider_tick_rate: f32,
 }
 
+impl DifficultySection {
+    /// Difficulty settings with the default values used by osu! for a new beatmap,
+    /// unlike [Default] which uses the zero value of each field.
+    pub fn osu_default() -> Self {
+        Self {
+            hp_drain_rate: 5.,
+            circle_size: 5.,
+            overall_difficulty: 5.,
+            approach_rate: 5.,
+            slider_multiplier: 1.4,
+            slider_tick_rate: 1.,
+        }
+    }
+
+    /// Allowed range of the circle size, which is the amount of columns in osu!mania.
+    fn circle_size_range(mode: &Gamemode) -> RangeInclusive<f32> {
+        match mode {
+            Gamemode::MANIA => 1.0..=18.0,
+            _ => 0.0..=10.0,
+        }
+    }
+
+    /// List the settings outside of their allowed range for the given game mode.
+    pub fn validate(&self, mode: &Gamemode) -> Vec<ValidationWarning> {
+        let fields = [
+            ("HPDrainRate", self.hp_drain_rate, 0.0..=10.0),
+            (
+                "CircleSize",
+                self.circle_size,
+                Self::circle_size_range(mode),
+            ),
+            ("OverallDifficulty", self.overall_difficulty, 0.0..=10.0),
+            ("ApproachRate", self.approach_rate, 0.0..=10.0),
+        ];
+
+        fields
+            .into_iter()
+            .filter(|(_, value, range)| !range.contains(value))
+            .map(|(field, value, _)| ValidationWarning::DifficultyOutOfRange { field, value })
+            .collect()
+    }
+
+    /// Force the settings into their allowed range for the given game mode.
+    pub fn clamp(&mut self, mode: &Gamemode) {
+        let circle_size_range = Self::circle_size_range(mode);
+
+        self.hp_drain_rate = self.hp_drain_rate.clamp(0., 10.);
+        self.circle_size = self
+            .circle_size
+            .clamp(*circle_size_range.start(), *circle_size_range.end());
+        self.overall_difficulty = self.overall_difficulty.clamp(0., 10.);
+        self.approach_rate = self.approach_rate.clamp(0., 10.);
+    }
+
+    /// Time in milliseconds between the appearance of a hit object and the moment it has to be
+    /// hit, given by the approach rate: 1800ms at AR0, 1200ms at AR5 and 450ms at AR10.
+    pub fn preempt(&self) -> f64 {
+        let approach_rate = self.approach_rate as f64;
+
+        match approach_rate < 5. {
+            true => 1200. + 600. * (5. - approach_rate) / 5.,
+            false => 1200. - 750. * (approach_rate - 5.) / 5.,
+        }
+    }
+
+    /// Maximum distance in milliseconds from the hit object time to score a 300 in osu!standard.
+    pub fn hit_window_300(&self) -> f64 {
+        80. - 6. * self.overall_difficulty as f64
+    }
+
+    /// Maximum distance in milliseconds from the hit object time to score a 100 in osu!standard.
+    pub fn hit_window_100(&self) -> f64 {
+        140. - 8. * self.overall_difficulty as f64
+    }
+
+    /// Maximum distance in milliseconds from the hit object time to score a 50 in osu!standard.
+    pub fn hit_window_50(&self) -> f64 {
+        200. - 10. * self.overall_difficulty as f64
+    }
+
+    /// Radius of the hit circles in [osu! pixels](https://osu.ppy.sh/wiki/en/osupixel),
+    /// given by the circle size.
+    ///
+    /// Only meaningful for osu!standard and osu!catch (where it's the size of the fruits),
+    /// osu!mania uses the circle size as its amount of columns and osu!taiko ignores it.
+    pub fn circle_radius(&self) -> f64 {
+        54.4 - 4.48 * self.circle_size as f64
+    }
+
+    /// Apply the difficulty changes of a mod to the settings.
+    pub fn apply_mod(&mut self, diff_mod: DiffMod) {
+        match diff_mod {
+            DiffMod::HardRock => {
+                self.circle_size = (self.circle_size * 1.3).min(10.);
+                self.approach_rate = (self.approach_rate * 1.4).min(10.);
+                self.overall_difficulty = (self.overall_difficulty * 1.4).min(10.);
+                self.hp_drain_rate = (self.hp_drain_rate * 1.4).min(10.);
+            }
+            DiffMod::Easy => {
+                self.circle_size *= 0.5;
+                self.approach_rate *= 0.5;
+                self.overall_difficulty *= 0.5;
+                self.hp_drain_rate *= 0.5;
+            }
+        }
+    }
+}
+
 impl FromStr for DifficultySection {
     type Err = BeatmapParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s: Vec<&str> = s.trim().split("\n").map(|x| x.trim()).collect();
+        Self::parse_with(s, &ParseOptions::default())
+    }
+}
+
+impl DifficultySection {
+    /// Parse the section with the given options.
+    pub fn parse_with(s: &str, options: &ParseOptions) -> Result<Self, BeatmapParseError> {
+        let s = KeyValueLines::new(s, options);
+        let lenient = options.lenient;
         let mut difficulty = Self::new();
 
-        difficulty.hp_drain_rate = Self::get_field_name_value(&s, "HPDrainRate")?;
-        difficulty.circle_size = Self::get_field_name_value(&s, "CircleSize")?;
-        difficulty.overall_difficulty = Self::get_field_name_value(&s, "OverallDifficulty")?;
-        difficulty.approach_rate = Self::get_field_name_value(&s, "ApproachRate")?;
-        difficulty.slider_multiplier = Self::get_field_name_value(&s, "SliderMultiplier")?;
-        difficulty.slider_tick_rate = Self::get_field_name_value(&s, "SliderTickRate")?;
+        difficulty.hp_drain_rate =
+            Self::get_decimal_field_value_or(&s, "HPDrainRate", 0., lenient)?;
+        difficulty.circle_size = Self::get_decimal_field_value_or(&s, "CircleSize", 0., lenient)?;
+        difficulty.overall_difficulty =
+            Self::get_decimal_field_value_or(&s, "OverallDifficulty", 0., lenient)?;
+        difficulty.approach_rate =
+            Self::get_decimal_field_value_or(&s, "ApproachRate", 0., lenient)?;
+        // Absent slider settings use the osu! defaults, a null value would break slider timing
+        let defaults = Self::osu_default();
+        difficulty.slider_multiplier = Self::get_decimal_field_value_or(
+            &s,
+            "SliderMultiplier",
+            defaults.slider_multiplier,
+            lenient,
+        )?;
+        difficulty.slider_tick_rate = Self::get_decimal_field_value_or(
+            &s,
+            "SliderTickRate",
+            defaults.slider_tick_rate,
+            lenient,
+        )?;
 
         Ok(difficulty)
     }
-}
 
-impl ToString for DifficultySection {
-    fn to_string(&self) -> String {
+    /// Serialize the section with the given options.
+    pub fn serialize_with(&self, options: &SerializeOptions) -> String {
         let mut buf = String::new();
+        let always = options.editor_fields;
 
-        Self::write_field_in(&mut buf, "HPDrainRate", &self.hp_drain_rate, false);
-        Self::write_field_in(&mut buf, "CircleSize", &self.circle_size, false);
-        Self::write_field_in(
+        Self::write_field_in_with(&mut buf, "HPDrainRate", &self.hp_drain_rate, false, always);
+        Self::write_field_in_with(&mut buf, "CircleSize", &self.circle_size, false, always);
+        Self::write_field_in_with(
             &mut buf,
             "OverallDifficulty",
             &self.overall_difficulty,
             false,
+            always,
+        );
+        Self::write_field_in_with(&mut buf, "ApproachRate", &self.approach_rate, false, always);
+        Self::write_field_in_with(
+            &mut buf,
+            "SliderMultiplier",
+            &self.slider_multiplier,
+            false,
+            always,
+        );
+        Self::write_field_in_with(
+            &mut buf,
+            "SliderTickRate",
+            &self.slider_tick_rate,
+            false,
+            always,
         );
-        Self::write_field_in(&mut buf, "ApproachRate", &self.approach_rate, false);
-        Self::write_field_in(&mut buf, "SliderMultiplier", &self.slider_multiplier, false);
-        Self::write_field_in(&mut buf, "SliderTickRate", &self.slider_tick_rate, false);
 
         buf
     }
 }
 
+impl ToString for DifficultySection {
+    fn to_string(&self) -> String {
+        self.serialize_with(&SerializeOptions::default())
+    }
+}
+
 impl Section for DifficultySection {}
 impl SectionKeyValue for DifficultySection {}
 
 #[cfg(test)]
 mod tests {
+    use crate::options::ParseOptions;
     use crate::section::difficulty::DifficultySection;
     use crate::section::Section;
+    use crate::types::difficulty::DiffMod;
+    use crate::types::general::Gamemode;
+    use crate::validation::ValidationWarning;
 
     const TEST_SECTION: &'static str = "HPDrainRate:5
 CircleSize:4
@@ -86,6 +245,94 @@ SliderTickRate:1
         assert_eq!(difficulty.slider_tick_rate, 1.0);
     }
 
+    #[test]
+    fn parse_difficulty_without_slider_settings() {
+        let difficulty =
+            DifficultySection::parse("HPDrainRate:5\nCircleSize:4\nOverallDifficulty:6\n").unwrap();
+
+        assert_eq!(difficulty.slider_multiplier, 1.4);
+        assert_eq!(difficulty.slider_tick_rate, 1.);
+        assert_eq!(difficulty.approach_rate, 0.);
+    }
+
+    #[test]
+    fn parse_difficulty_with_comma_decimal() {
+        let section = TEST_SECTION.replace("OverallDifficulty:6", "OverallDifficulty:6,5");
+        let lenient = ParseOptions::new().lenient(true);
+
+        assert!(DifficultySection::parse(&section).is_err());
+        assert_eq!(
+            DifficultySection::parse_with(&section, &lenient)
+                .unwrap()
+                .overall_difficulty,
+            6.5
+        );
+        assert!(DifficultySection::parse_with("ApproachRate:1,5,0", &lenient).is_err());
+    }
+
+    #[test]
+    fn validate_difficulty() {
+        let difficulty = DifficultySection::parse(TEST_SECTION).unwrap();
+
+        assert!(difficulty.validate(&Gamemode::STD).is_empty());
+    }
+
+    #[test]
+    fn validate_clamp_out_of_range_difficulty() {
+        let mut difficulty =
+            DifficultySection::parse("HPDrainRate:5\nCircleSize:12\nOverallDifficulty:99").unwrap();
+
+        assert_eq!(
+            difficulty.validate(&Gamemode::STD),
+            vec![
+                ValidationWarning::DifficultyOutOfRange {
+                    field: "CircleSize",
+                    value: 12.
+                },
+                ValidationWarning::DifficultyOutOfRange {
+                    field: "OverallDifficulty",
+                    value: 99.
+                }
+            ]
+        );
+        assert_eq!(
+            difficulty.validate(&Gamemode::MANIA),
+            vec![ValidationWarning::DifficultyOutOfRange {
+                field: "OverallDifficulty",
+                value: 99.
+            }]
+        );
+
+        difficulty.clamp(&Gamemode::STD);
+
+        assert_eq!(difficulty.circle_size, 10.);
+        assert_eq!(difficulty.overall_difficulty, 10.);
+        assert!(difficulty.validate(&Gamemode::STD).is_empty());
+    }
+
+    #[test]
+    fn apply_hard_rock() {
+        let mut difficulty = DifficultySection::parse(TEST_SECTION).unwrap();
+        difficulty.overall_difficulty = 7.0;
+        difficulty.apply_mod(DiffMod::HardRock);
+
+        assert_eq!(difficulty.overall_difficulty, 9.8);
+        assert_eq!(difficulty.hp_drain_rate, 7.0);
+        assert_eq!(difficulty.circle_size, 5.2);
+        assert_eq!(difficulty.approach_rate, 10.0);
+    }
+
+    #[test]
+    fn apply_easy() {
+        let mut difficulty = DifficultySection::parse(TEST_SECTION).unwrap();
+        difficulty.apply_mod(DiffMod::Easy);
+
+        assert_eq!(difficulty.hp_drain_rate, 2.5);
+        assert_eq!(difficulty.circle_size, 2.0);
+        assert_eq!(difficulty.overall_difficulty, 3.0);
+        assert_eq!(difficulty.approach_rate, 4.0);
+    }
+
     #[test]
     fn serialize_difficulty() {
         let mut difficulty = DifficultySection::new();
@@ -100,4 +347,32 @@ SliderTickRate:1
 
         assert_eq!(serialized_difficulty, TEST_SECTION)
     }
+
+    #[test]
+    fn preempt() {
+        let mut difficulty = DifficultySection::parse(TEST_SECTION).unwrap();
+
+        assert_eq!(difficulty.preempt(), 750.);
+
+        for (approach_rate, preempt) in [(0., 1800.), (5., 1200.), (9., 600.), (10., 450.)] {
+            difficulty.approach_rate = approach_rate;
+            assert_eq!(difficulty.preempt(), preempt);
+        }
+    }
+
+    #[test]
+    fn hit_windows() {
+        let difficulty = DifficultySection::parse(TEST_SECTION).unwrap();
+
+        assert_eq!(difficulty.hit_window_300(), 44.);
+        assert_eq!(difficulty.hit_window_100(), 92.);
+        assert_eq!(difficulty.hit_window_50(), 140.);
+    }
+
+    #[test]
+    fn circle_radius() {
+        let difficulty = DifficultySection::parse(TEST_SECTION).unwrap();
+
+        assert!((difficulty.circle_radius() - 36.48).abs() < 1e-9);
+    }
 }
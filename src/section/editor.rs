@@ -1,13 +1,17 @@
 use crate::error::BeatmapParseError;
 use crate::error::BeatmapParseError::InvalidFormat;
-use crate::section::{Section, SectionKeyValue};
+use crate::options::{ParseOptions, SerializeOptions};
+use crate::section::{KeyValueLines, Section, SectionKeyValue};
 use std::str::FromStr;
 
 /// Saved settings for the beatmap editor
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct EditorSection {
     /// Time in milliseconds of
     /// [bookmarks](https://osu.ppy.sh/wiki/en/Client/Beatmap_editor/Compose#bottom-(song's-timeline))
+    ///
+    /// When parsing a whole beatmap, the legacy `EditorBookmarks` of the `[General]` section are
+    /// used if this section has none, and written back here.
     pub bookmarks: Vec<i32>,
     /// [Distance snap](https://osu.ppy.sh/wiki/en/Client/Beatmap_editor/Distance_snap) multiplier
     pub distance_spacing: f32,
@@ -28,21 +32,18 @@ impl FromStr for EditorSection {
     type Err = BeatmapParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s: Vec<&str> = s.trim().split("\n").map(|x| x.trim()).collect();
+        Self::parse_with(s, &ParseOptions::default())
+    }
+}
+
+impl EditorSection {
+    /// Parse the section with the given options.
+    pub fn parse_with(s: &str, options: &ParseOptions) -> Result<Self, BeatmapParseError> {
+        let s = KeyValueLines::new(s, options);
         let mut editor = Self::default();
 
         let bookmarks: String = Self::get_field_name_value(&s, "Bookmarks")?;
-
-        editor.bookmarks = bookmarks
-            .split(',')
-            .map(|x| {
-                i32::from_str(x)
-                    .map_err(|_| InvalidFormat {
-                        field: "Bookmarks".to_string(),
-                    })
-                    .unwrap()
-            })
-            .collect();
+        editor.bookmarks = Self::parse_bookmarks(&bookmarks, "Bookmarks")?;
         editor.distance_spacing = Self::get_field_name_value(&s, "DistanceSpacing")?;
         editor.beat_divisor = Self::get_field_name_value(&s, "BeatDivisor")?;
         editor.grid_size = Self::get_field_name_value(&s, "GridSize")?;
@@ -50,11 +51,35 @@ impl FromStr for EditorSection {
 
         Ok(editor)
     }
-}
 
-impl ToString for EditorSection {
-    fn to_string(&self) -> String {
+    fn parse_bookmarks(bookmarks: &str, field_name: &str) -> Result<Vec<i32>, BeatmapParseError> {
+        bookmarks
+            .split(',')
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| {
+                i32::from_str(x.trim()).map_err(|_| InvalidFormat {
+                    field: field_name.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Bookmarks stored by very old beatmaps in the `[General]` section as `EditorBookmarks`,
+    /// before they moved to `Bookmarks` in the `[Editor]` section.
+    pub(crate) fn legacy_bookmarks(
+        general: &str,
+        options: &ParseOptions,
+    ) -> Result<Vec<i32>, BeatmapParseError> {
+        let s = KeyValueLines::new(general, options);
+        let bookmarks: String = Self::get_field_name_value(&s, "EditorBookmarks")?;
+
+        Self::parse_bookmarks(&bookmarks, "EditorBookmarks")
+    }
+
+    /// Serialize the section with the given options.
+    pub fn serialize_with(&self, options: &SerializeOptions) -> String {
         let mut buf = String::new();
+        let always = options.editor_fields;
         let mut bookmarks = String::new();
 
         for bookmark in self.bookmarks.iter() {
@@ -67,15 +92,27 @@ impl ToString for EditorSection {
         }
 
         Self::write_field_in(&mut buf, "Bookmarks", &bookmarks, true);
-        Self::write_field_in(&mut buf, "DistanceSpacing", &self.distance_spacing, true);
-        Self::write_field_in(&mut buf, "BeatDivisor", &self.beat_divisor, true);
-        Self::write_field_in(&mut buf, "GridSize", &self.grid_size, true);
-        Self::write_field_in(&mut buf, "TimelineZoom", &self.timeline_zoom, true);
+        Self::write_field_in_with(
+            &mut buf,
+            "DistanceSpacing",
+            &self.distance_spacing,
+            true,
+            always,
+        );
+        Self::write_field_in_with(&mut buf, "BeatDivisor", &self.beat_divisor, true, always);
+        Self::write_field_in_with(&mut buf, "GridSize", &self.grid_size, true, always);
+        Self::write_field_in_with(&mut buf, "TimelineZoom", &self.timeline_zoom, true, always);
 
         buf
     }
 }
 
+impl ToString for EditorSection {
+    fn to_string(&self) -> String {
+        self.serialize_with(&SerializeOptions::default())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Section;
@@ -100,6 +137,15 @@ TimelineZoom: 1.6
         assert_eq!(editor.timeline_zoom, 1.6);
     }
 
+    #[test]
+    fn parse_empty_bookmarks() {
+        let editor = EditorSection::parse("Bookmarks: \nBeatDivisor: 4").unwrap();
+        assert!(editor.bookmarks.is_empty());
+
+        let editor = EditorSection::parse("Bookmarks: 1000, ,2000,").unwrap();
+        assert_eq!(editor.bookmarks, vec![1000, 2000]);
+    }
+
     #[test]
     fn serialize_editor() {
         let mut editor = EditorSection::new();
@@ -1,7 +1,8 @@
 use crate::error::BeatmapParseError::InvalidFormat;
 use crate::error::{BeatmapParseError, EventsError};
-use crate::section::CommaListElement;
-use std::fmt::{Debug, Display, Formatter};
+use crate::section::storyboard::Storyboard;
+use crate::section::{CommaListElement, CommaListOf};
+use std::fmt::{Debug, Display, Formatter, Write};
 use std::str::FromStr;
 
 /// Type of an event with the wrapped event params.
@@ -47,12 +48,22 @@ impl EventType {
     pub fn try_into_inner<T: TryFrom<Self>>(self) -> Result<T, T::Error> {
         self.try_into()
     }
+
+    /// Name of the event type as written in the beatmap file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventType::Background(_) => "Background",
+            EventType::Video(_) => "Video",
+            EventType::Break(_) => "Break",
+        }
+    }
 }
 
 impl Display for EventType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             EventType::Background(_) => write!(f, "0"),
+            EventType::Video(x) if x.named => write!(f, "Video"),
             EventType::Video(_) => write!(f, "1"),
             EventType::Break(_) => write!(f, "2"),
         }
@@ -69,6 +80,17 @@ pub struct BackgroundParams {
     pub x_offset: i32,
     /// Y offset in [osu! pixels](https://osu.ppy.sh/wiki/en/osupixel) from the centre of the screen.
     pub y_offset: i32,
+    /// Whether the offsets were written in the source line.
+    /// Offsets which are both `0` are only serialized when this is set, so parsed backgrounds
+    /// are written back as they were.
+    pub explicit_offsets: bool,
+}
+
+impl BackgroundParams {
+    /// Stop writing the offsets when both are `0`, as for a newly created background.
+    pub fn normalize_offsets(&mut self) {
+        self.explicit_offsets = false;
+    }
 }
 
 impl From<BackgroundParams> for EventType {
@@ -78,18 +100,25 @@ impl From<BackgroundParams> for EventType {
 }
 
 impl TryFrom<EventType> for BackgroundParams {
-    type Error = ();
+    type Error = EventsError;
 
     fn try_from(value: EventType) -> Result<Self, Self::Error> {
         match value {
             EventType::Background(x) => Ok(x),
-            _ => Err(()),
+            _ => Err(EventsError::EventTypeMismatch {
+                expected: "Background",
+                found: value.name(),
+            }),
         }
     }
 }
 
 impl Display for BackgroundParams {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if !self.explicit_offsets && self.x_offset == 0 && self.y_offset == 0 {
+            return write!(f, "\"{}\"", self.filename);
+        }
+
         write!(
             f,
             "\"{}\",{},{}",
@@ -108,6 +137,12 @@ pub struct VideoParams {
     pub x_offset: i32,
     /// Y offset in [osu! pixels](https://osu.ppy.sh/wiki/en/osupixel) from the centre of the screen.
     pub y_offset: i32,
+    /// Whether the offsets were written in the source line.
+    /// Offsets which are both `0` are only serialized when this is set, so parsed videos
+    /// are written back as they were.
+    pub explicit_offsets: bool,
+    /// Whether the event type is written as `Video`, as the osu! editor does, rather than `1`.
+    pub named: bool,
 }
 
 impl From<VideoParams> for EventType {
@@ -117,18 +152,25 @@ impl From<VideoParams> for EventType {
 }
 
 impl TryFrom<EventType> for VideoParams {
-    type Error = ();
+    type Error = EventsError;
 
     fn try_from(value: EventType) -> Result<Self, Self::Error> {
         match value {
             EventType::Video(x) => Ok(x),
-            _ => Err(()),
+            _ => Err(EventsError::EventTypeMismatch {
+                expected: "Video",
+                found: value.name(),
+            }),
         }
     }
 }
 
 impl Display for VideoParams {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if !self.explicit_offsets && self.x_offset == 0 && self.y_offset == 0 {
+            return write!(f, "\"{}\"", self.filename);
+        }
+
         write!(
             f,
             "\"{}\",{},{}",
@@ -143,6 +185,8 @@ impl Display for VideoParams {
 pub struct BreakParams {
     /// End time of the break, in milliseconds from the beginning of the beatmap's audio.
     pub end_time: u32,
+    /// Fields following the end time, not used by osu! but kept as is to be written back.
+    pub trailing: Vec<String>,
 }
 
 impl From<BreakParams> for EventType {
@@ -152,26 +196,34 @@ impl From<BreakParams> for EventType {
 }
 
 impl TryFrom<EventType> for BreakParams {
-    type Error = ();
+    type Error = EventsError;
 
     fn try_from(value: EventType) -> Result<Self, Self::Error> {
         match value {
             EventType::Break(x) => Ok(x),
-            _ => Err(()),
+            _ => Err(EventsError::EventTypeMismatch {
+                expected: "Break",
+                found: value.name(),
+            }),
         }
     }
 }
 
 impl Display for BreakParams {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.end_time)
+        write!(f, "{}", self.end_time)?;
+
+        for field in self.trailing.iter() {
+            write!(f, ",{}", field)?;
+        }
+        Ok(())
     }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Beatmap graphic event
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq, Eq)]
 pub struct Event {
     /// Start time of the event, in milliseconds from the beginning of the beatmap's audio.
     /// For events that do not use a start time, the default is `0`.
@@ -180,6 +232,27 @@ pub struct Event {
     pub event_params: EventType,
 }
 
+/// Split an event line on its commas, except the ones inside a quoted filename.
+/// Quotes are ignored if they are unbalanced, as they can't delimit the filename.
+fn split_fields(s: &str) -> impl Iterator<Item = &str> {
+    let balanced = s.matches('"').count() & 1 == 0;
+    let mut in_quotes = false;
+
+    s.split(move |c| {
+        if c == '"' && balanced {
+            in_quotes = !in_quotes;
+        }
+        c == ',' && !in_quotes
+    })
+}
+
+/// Remove the quotes surrounding a filename, at most one on each side, keeping the quotes
+/// inside the filename intact.
+fn unquote(s: &str) -> &str {
+    let s = s.strip_prefix('"').unwrap_or(s);
+    s.strip_suffix('"').unwrap_or(s)
+}
+
 impl FromStr for Event {
     type Err = BeatmapParseError;
 
@@ -188,55 +261,74 @@ impl FromStr for Event {
             return Err(BeatmapParseError::CommentaryEntry);
         }
 
-        if s.starts_with("Sprite")
-            || s.starts_with("Animation")
-            || s.starts_with("F")
-            || s.starts_with("M")
-            || s.starts_with("MX")
-            || s.starts_with("MY")
-            || s.starts_with("S")
-            || s.starts_with("V")
-            || s.starts_with("R")
-            || s.starts_with("C")
-            || s.starts_with("P")
+        // Compare the whole keyword, so a `Video` event isn't mistaken for a `V` command.
+        // Commands are indented with underscores or spaces under their object.
+        let keyword = s
+            .split(',')
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches([' ', '_'])
+            .trim();
+        if keyword.starts_with("Sprite")
+            || keyword.starts_with("Animation")
+            || keyword.starts_with("Sample")
+            || ["F", "M", "MX", "MY", "S", "V", "R", "C", "P", "L", "T"].contains(&keyword)
         {
             return Err(BeatmapParseError::StoryboardEntry);
         }
 
-        let s: Vec<&str> = s.trim().split(",").map(|x| x.trim()).collect();
+        let s: Vec<&str> = split_fields(s.trim()).map(|x| x.trim()).collect();
+        // Missing fields are reported as invalid rather than indexed blindly, and any
+        // trailing fields past the ones an event type uses are ignored, except for breaks
+        // which keep them.
+        let field = |i: usize| {
+            s.get(i).copied().ok_or(InvalidFormat {
+                field: i.to_string(),
+            })
+        };
 
-        let mut event_type = EventType::from_str(s[0]).map_err(|_| InvalidFormat {
+        let mut event_type = EventType::from_str(field(0)?).map_err(|_| InvalidFormat {
             field: String::from("0"),
         })?;
 
         match event_type {
             EventType::Background(ref mut x) => {
-                x.filename = String::from(s[2].trim_matches('\"'));
-                x.x_offset = i32::from_str(s[3]).map_err(|_| InvalidFormat {
-                    field: String::from("3"),
-                })?;
-                x.y_offset = i32::from_str(s[4]).map_err(|_| InvalidFormat {
-                    field: String::from("4"),
-                })?;
+                x.filename = String::from(unquote(field(2)?));
+                x.explicit_offsets = s.len() > 3;
+                if x.explicit_offsets {
+                    x.x_offset = i32::from_str(field(3)?).map_err(|_| InvalidFormat {
+                        field: String::from("3"),
+                    })?;
+                    x.y_offset = i32::from_str(field(4)?).map_err(|_| InvalidFormat {
+                        field: String::from("4"),
+                    })?;
+                }
             }
             EventType::Video(ref mut x) => {
-                x.filename = String::from(s[2].trim_matches('\"'));
-                x.x_offset = i32::from_str(s[3]).map_err(|_| InvalidFormat {
-                    field: String::from("3"),
-                })?;
-                x.y_offset = i32::from_str(s[4]).map_err(|_| InvalidFormat {
-                    field: String::from("4"),
-                })?;
+                x.filename = String::from(unquote(field(2)?));
+                x.named = field(0)? == "Video";
+                x.explicit_offsets = s.len() > 3;
+                if x.explicit_offsets {
+                    x.x_offset = i32::from_str(field(3)?).map_err(|_| InvalidFormat {
+                        field: String::from("3"),
+                    })?;
+                    x.y_offset = i32::from_str(field(4)?).map_err(|_| InvalidFormat {
+                        field: String::from("4"),
+                    })?;
+                }
             }
             EventType::Break(ref mut x) => {
-                x.end_time = u32::from_str(s[2]).map_err(|_| InvalidFormat {
+                x.end_time = u32::from_str(field(2)?).map_err(|_| InvalidFormat {
                     field: String::from("2"),
                 })?;
+                // A trailing comma doesn't make an empty field worth keeping
+                let end = s.iter().rposition(|x| !x.is_empty()).map_or(0, |i| i + 1);
+                x.trailing = s.iter().take(end).skip(3).map(|x| x.to_string()).collect();
             }
         }
 
         Ok(Event {
-            start_time: u32::from_str(s[1]).map_err(|_| InvalidFormat {
+            start_time: u32::from_str(field(1)?).map_err(|_| InvalidFormat {
                 field: String::from("1"),
             })?,
             event_params: event_type,
@@ -244,26 +336,62 @@ impl FromStr for Event {
     }
 }
 
-impl ToString for Event {
-    fn to_string(&self) -> String {
-        format!(
-            "{},{},{}",
-            self.event_params.to_string(),
-            self.start_time.to_string(),
-            self.event_params.serialize_inner()
-        )
+impl Display for Event {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},", self.event_params, self.start_time)?;
+
+        match &self.event_params {
+            EventType::Background(x) => write!(f, "{}", x),
+            EventType::Video(x) => write!(f, "{}", x),
+            EventType::Break(x) => write!(f, "{}", x),
+        }
     }
 }
 
 impl CommaListElement for Event {}
 
+impl CommaListOf<Event> {
+    /// Serialize the events grouped under the comment headers written by the osu! editor,
+    /// followed by the storyboard entries of the section.
+    pub fn to_stable_string(&self, storyboard: &Storyboard) -> String {
+        let mut buf = String::new();
+
+        // Writing into a String can't fail
+        self.write_stable(&mut buf, storyboard).unwrap();
+
+        buf
+    }
+
+    fn write_stable(&self, buf: &mut String, storyboard: &Storyboard) -> std::fmt::Result {
+        writeln!(buf, "//Background and Video events")?;
+        for event in self
+            .iter()
+            .filter(|x| !matches!(x.event_params, EventType::Break(_)))
+        {
+            writeln!(buf, "{}", event)?;
+        }
+
+        writeln!(buf, "//Break Periods")?;
+        for event in self
+            .iter()
+            .filter(|x| matches!(x.event_params, EventType::Break(_)))
+        {
+            writeln!(buf, "{}", event)?;
+        }
+
+        write!(buf, "{}", storyboard)
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 #[cfg(test)]
 mod tests {
+    use crate::error::EventsError;
     use crate::section::events::{BackgroundParams, BreakParams, Event, EventType};
+    use crate::section::storyboard::Storyboard;
     use crate::section::CommaListOf;
     use crate::section::Section;
 
@@ -294,6 +422,21 @@ mod tests {
         assert_eq!(second_event_params.end_time, 114656);
     }
 
+    #[test]
+    fn skip_indented_storyboard_commands() {
+        let section = "0,0,\"bg.jpg\",0,0
+Sprite,Background,Centre,\"sb/bg.png\",320,240
+_F,0,1000,2000,0,1
+ M,0,1000,2000,0,0,320,240
+__L,1000,2
+  F,0,0,500,1,0
+2,104177,114656
+";
+        let events: CommaListOf<Event> = CommaListOf::parse(section).unwrap();
+
+        assert_eq!(events.len(), 2);
+    }
+
     #[test]
     fn serialize_events() {
         let mut events: CommaListOf<Event> = CommaListOf::new();
@@ -303,11 +446,15 @@ mod tests {
                 filename: String::from("bg.jpg"),
                 x_offset: 0,
                 y_offset: 0,
+                explicit_offsets: true,
             }),
         };
         let second_event = Event {
             start_time: 104177,
-            event_params: EventType::Break(BreakParams { end_time: 114656 }),
+            event_params: EventType::Break(BreakParams {
+                end_time: 114656,
+                ..Default::default()
+            }),
         };
 
         events.push(first_event);
@@ -316,11 +463,54 @@ mod tests {
         assert_eq!(events.serialize(), TEST_SECTION)
     }
 
+    #[test]
+    fn serialize_events_with_headers() {
+        let events: CommaListOf<Event> = CommaListOf::parse(TEST_SECTION).unwrap();
+
+        assert_eq!(
+            events.to_stable_string(&Storyboard::default()),
+            "//Background and Video events
+0,0,\"bg.jpg\",0,0
+//Break Periods
+2,104177,114656
+//Storyboard Layer 0 (Background)
+//Storyboard Layer 1 (Fail)
+//Storyboard Layer 2 (Pass)
+//Storyboard Layer 3 (Foreground)
+//Storyboard Layer 4 (Overlay)
+//Storyboard Sound Samples
+"
+        );
+    }
+
+    #[test]
+    fn event_type_mismatch() {
+        let events: CommaListOf<Event> = CommaListOf::parse(TEST_SECTION).unwrap();
+        let err = events[0]
+            .event_params
+            .clone()
+            .try_into_inner::<BreakParams>()
+            .unwrap_err();
+
+        assert_eq!(
+            err,
+            EventsError::EventTypeMismatch {
+                expected: "Break",
+                found: "Background"
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Tried to convert an event to the wrong type, expected Break, found Background"
+        );
+    }
+
     mod event {
-        use crate::section::events::{BackgroundParams, Event};
+        use crate::section::events::{BackgroundParams, BreakParams, Event, VideoParams};
         use crate::section::CommaListElement;
 
         const TEST_BACKGROUND_EVENT: &'static str = "0,0,\"bg.jpg\",0,0";
+        const TEST_BREAK_EVENT: &str = "2,104177,114656";
 
         #[test]
         fn parse_background_event() {
@@ -333,6 +523,24 @@ mod tests {
             assert_eq!(event_params.y_offset, 0);
         }
 
+        #[test]
+        fn round_trip_video_event() {
+            let event = Event::parse("Video,0,\"clip.mp4\"").unwrap();
+            let event_params: VideoParams = event.event_params.clone().try_into_inner().unwrap();
+
+            assert_eq!(event_params.filename, "clip.mp4");
+            assert_eq!((event_params.x_offset, event_params.y_offset), (0, 0));
+            assert_eq!(event.serialize(), "Video,0,\"clip.mp4\"");
+
+            for line in [
+                "1,1500,\"clip.mp4\"",
+                "1,0,\"clip.mp4\",0,0",
+                "Video,0,\"clip.mp4\",10,20",
+            ] {
+                assert_eq!(Event::parse(line).unwrap().serialize(), line);
+            }
+        }
+
         #[test]
         fn serialize_background_event() {
             let mut event = Event::new();
@@ -341,7 +549,125 @@ mod tests {
             event_params.filename = String::from("bg.jpg");
             event.event_params = event_params.into();
 
-            assert_eq!(event.serialize(), TEST_BACKGROUND_EVENT)
+            assert_eq!(event.serialize(), "0,0,\"bg.jpg\"")
+        }
+
+        #[test]
+        fn round_trip_background_event_offsets() {
+            let event = Event::parse(TEST_BACKGROUND_EVENT).unwrap();
+
+            assert_eq!(event.serialize(), TEST_BACKGROUND_EVENT);
+
+            let mut event_params: BackgroundParams = event.event_params.try_into_inner().unwrap();
+            event_params.normalize_offsets();
+
+            assert_eq!(event_params.to_string(), "\"bg.jpg\"");
+        }
+
+        #[test]
+        fn keep_non_zero_background_offsets() {
+            let event = Event::parse("0,0,\"bg.jpg\",0,-20").unwrap();
+            let mut event_params: BackgroundParams =
+                event.event_params.clone().try_into_inner().unwrap();
+            event_params.normalize_offsets();
+
+            assert_eq!(event.serialize(), "0,0,\"bg.jpg\",0,-20");
+            assert_eq!(event_params.to_string(), "\"bg.jpg\",0,-20");
+        }
+
+        #[test]
+        fn parse_background_event_without_offsets() {
+            let event = Event::parse("0,0,\"bg.jpg\"").unwrap();
+            let event_params: BackgroundParams =
+                event.event_params.clone().try_into_inner().unwrap();
+
+            assert_eq!(event_params.filename, "bg.jpg");
+            assert_eq!(event_params.x_offset, 0);
+            assert_eq!(event_params.y_offset, 0);
+            assert_eq!(event.serialize(), "0,0,\"bg.jpg\"");
+        }
+
+        #[test]
+        fn serialize_moved_background_event_without_offsets() {
+            let mut event = Event::parse("0,0,\"bg.jpg\"").unwrap();
+            let mut event_params: BackgroundParams = event.event_params.try_into_inner().unwrap();
+            event_params.x_offset = 10;
+            event.event_params = event_params.into();
+
+            assert_eq!(event.serialize(), "0,0,\"bg.jpg\",10,0");
+        }
+
+        #[test]
+        fn parse_background_event_with_quotes_in_filename() {
+            let event = Event::parse("0,0,\"a\"b\".jpg\",0,0").unwrap();
+            let event_params: BackgroundParams =
+                event.event_params.clone().try_into_inner().unwrap();
+
+            assert_eq!(event_params.filename, "a\"b\".jpg");
+            assert_eq!(event.serialize(), "0,0,\"a\"b\".jpg\",0,0");
+        }
+
+        #[test]
+        fn parse_background_event_with_unbalanced_quotes() {
+            let event = Event::parse("0,0,\"bg.jpg,0,0").unwrap();
+            let event_params: BackgroundParams =
+                event.event_params.clone().try_into_inner().unwrap();
+
+            assert_eq!(event_params.filename, "bg.jpg");
+            assert_eq!(event_params.x_offset, 0);
+        }
+
+        #[test]
+        fn parse_unquoted_background_event() {
+            let event = Event::parse("0,0,bg.jpg,0,0").unwrap();
+            let event_params: BackgroundParams =
+                event.event_params.clone().try_into_inner().unwrap();
+
+            assert_eq!(event_params.filename, "bg.jpg");
+            assert_eq!(event.serialize(), "0,0,\"bg.jpg\",0,0");
+        }
+
+        #[test]
+        fn parse_background_event_with_comma_in_filename() {
+            let event = Event::parse("0,0,\"my, song bg.jpg\",0,0").unwrap();
+            let event_params: BackgroundParams =
+                event.event_params.clone().try_into_inner().unwrap();
+
+            assert_eq!(event_params.filename, "my, song bg.jpg");
+            assert_eq!(event_params.x_offset, 0);
+            assert_eq!(event.serialize(), "0,0,\"my, song bg.jpg\",0,0");
+        }
+
+        #[test]
+        fn round_trip_break_event() {
+            let event = Event::parse(TEST_BREAK_EVENT).unwrap();
+
+            assert_eq!(event.start_time, 104177);
+            assert_eq!(event.serialize(), TEST_BREAK_EVENT);
+        }
+
+        #[test]
+        fn parse_break_event_with_trailing_fields() {
+            let event = Event::parse("2,104177,114656,").unwrap();
+            let event_params: BreakParams = event.event_params.clone().try_into_inner().unwrap();
+
+            assert_eq!(event.start_time, 104177);
+            assert_eq!(event_params.end_time, 114656);
+            assert_eq!(event.serialize(), TEST_BREAK_EVENT);
+        }
+
+        #[test]
+        fn round_trip_break_event_with_extra_field() {
+            let event = Event::parse("2,104177,114656,1").unwrap();
+            let event_params: BreakParams = event.event_params.clone().try_into_inner().unwrap();
+
+            assert_eq!(event_params.trailing, vec!["1"]);
+            assert_eq!(event.serialize(), "2,104177,114656,1");
+        }
+
+        #[test]
+        fn parse_truncated_break_event() {
+            assert!(Event::parse("2,104177").is_err());
         }
     }
 }
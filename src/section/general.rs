@@ -1,12 +1,13 @@
 use crate::error::BeatmapParseError;
-use crate::section::{Section, SectionKeyValue};
+use crate::options::{ParseOptions, SerializeOptions};
+use crate::section::{KeyValueLines, Section, SectionKeyValue};
 use crate::types::general::*;
 use crate::types::OsuBool;
 use std::i32;
 use std::str::FromStr;
 
 /// General information about the beatmap
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct GeneralSection {
     /// Location of the audio file relative to the current folder
     pub audio_filename: String,
@@ -51,24 +52,55 @@ pub struct GeneralSection {
     pub sample_match_pb_rate: OsuBool,
 }
 
+impl GeneralSection {
+    /// General section with the default values used by osu! when a field is absent,
+    /// unlike [Default] which uses the zero value of each field.
+    pub fn osu_default() -> Self {
+        Self {
+            audio_lead_in: 0,
+            preview_time: -1,
+            countdown: Countdown::NORMAL,
+            sample_set: SampleSet::NORMAL,
+            stack_leniency: 0.7,
+            mode: Gamemode::STD,
+            ..Self::default()
+        }
+    }
+
+    /// Whether or not the beatmap references an audio file.
+    pub fn has_audio(&self) -> bool {
+        !self.audio_filename.trim().is_empty()
+    }
+}
+
 impl Section for GeneralSection {}
 impl SectionKeyValue for GeneralSection {}
 
 impl FromStr for GeneralSection {
     type Err = BeatmapParseError;
 
-    #[allow(deprecated)]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s: Vec<&str> = s.trim().split("\n").map(|x| x.trim()).collect();
+        Self::parse_with(s, &ParseOptions::default())
+    }
+}
+
+impl GeneralSection {
+    /// Parse the section with the given options.
+    #[allow(deprecated)]
+    pub fn parse_with(s: &str, options: &ParseOptions) -> Result<Self, BeatmapParseError> {
+        let s = KeyValueLines::new(s, options);
         let mut general = Self::default();
 
         general.audio_filename = Self::get_field_name_value(&s, "AudioFilename")?;
-        general.audio_lead_in = Self::get_field_name_value(&s, "AudioLeadIn")?;
+        general.audio_lead_in =
+            Self::get_integer_field_value_or(&s, "AudioLeadIn", 0, options.lenient)?;
         general.audio_hash = Self::get_field_name_value(&s, "AudioHash")?;
-        general.preview_time = Self::get_field_name_value(&s, "PreviewTime")?;
+        general.preview_time =
+            Self::get_integer_field_value_or(&s, "PreviewTime", 0, options.lenient)?;
         general.countdown = Self::get_field_name_value(&s, "Countdown")?;
         general.sample_set = Self::get_field_name_value(&s, "SampleSet")?;
-        general.stack_leniency = Self::get_field_name_value(&s, "StackLeniency")?;
+        general.stack_leniency =
+            Self::get_decimal_field_value_or(&s, "StackLeniency", 0., options.lenient)?;
         general.mode = Self::get_field_name_value(&s, "Mode")?;
         general.lb_in_breaks = Self::get_field_name_value(&s, "LetterboxInBreaks")?;
         general.story_fire_in_front = Self::get_field_name_value(&s, "StoryFireInFront")?;
@@ -77,43 +109,69 @@ impl FromStr for GeneralSection {
         general.overlay_pos = Self::get_field_name_value(&s, "OverlayPosition")?;
         general.skin_preference = Self::get_field_name_value(&s, "SkinPreference")?;
         general.epilepsy_warn = Self::get_field_name_value(&s, "EpilepsyWarning")?;
-        general.countdown_offset = Self::get_field_name_value(&s, "CountdownOffset")?;
+        general.countdown_offset =
+            Self::get_integer_field_value_or(&s, "CountdownOffset", 0, options.lenient)?;
         general.special_style = Self::get_field_name_value(&s, "SpecialStyle")?;
         general.widescreen_sb = Self::get_field_name_value(&s, "WidescreenStoryboard")?;
         general.sample_match_pb_rate = Self::get_field_name_value(&s, "SamplesMatchPlaybackRate")?;
 
         Ok(general)
     }
-}
 
-impl ToString for GeneralSection {
+    /// Serialize the section with the given options.
     #[allow(deprecated)]
-    fn to_string(&self) -> String {
+    pub fn serialize_with(&self, options: &SerializeOptions) -> String {
         let mut buf = String::new();
+        let emit_deprecated = options.emit_deprecated;
+        let always = options.editor_fields;
 
         Self::write_field_in(&mut buf, "AudioFilename", &self.audio_filename, true);
-        Self::write_field_in(&mut buf, "AudioLeadIn", &self.audio_lead_in, true);
-        Self::write_field_in(&mut buf, "AudioHash", &self.audio_hash, true);
-        Self::write_field_in(&mut buf, "PreviewTime", &self.preview_time, true);
-        Self::write_field_in(&mut buf, "Countdown", &self.countdown, true);
-        Self::write_field_in(&mut buf, "SampleSet", &self.sample_set, true);
-        Self::write_field_in(&mut buf, "StackLeniency", &self.stack_leniency, true);
-        Self::write_field_in(&mut buf, "Mode", &self.mode, true);
-        Self::write_field_in(&mut buf, "LetterboxInBreaks", &self.lb_in_breaks, true);
-        Self::write_field_in(
+        Self::write_field_in_with(&mut buf, "AudioLeadIn", &self.audio_lead_in, true, always);
+        if emit_deprecated {
+            Self::write_field_in(&mut buf, "AudioHash", &self.audio_hash, true);
+        }
+        Self::write_field_in_with(&mut buf, "PreviewTime", &self.preview_time, true, always);
+        Self::write_field_in_with(&mut buf, "Countdown", &self.countdown, true, always);
+        Self::write_field_in_with(&mut buf, "SampleSet", &self.sample_set, true, always);
+        Self::write_field_in_with(
             &mut buf,
-            "StoryFireInFront",
-            &self.story_fire_in_front,
+            "StackLeniency",
+            &self.stack_leniency,
             true,
+            always,
         );
+        Self::write_field_in_with(&mut buf, "Mode", &self.mode, true, always);
+        Self::write_field_in_with(
+            &mut buf,
+            "LetterboxInBreaks",
+            &self.lb_in_breaks,
+            true,
+            always,
+        );
+        if emit_deprecated {
+            Self::write_field_in(
+                &mut buf,
+                "StoryFireInFront",
+                &self.story_fire_in_front,
+                true,
+            );
+        }
         Self::write_field_in(&mut buf, "UseSkinSprites", &self.use_skin_sprites, true);
-        Self::write_field_in(&mut buf, "AlwaysShowPlayfield", &self.show_playfield, true);
+        if emit_deprecated {
+            Self::write_field_in(&mut buf, "AlwaysShowPlayfield", &self.show_playfield, true);
+        }
         Self::write_field_in(&mut buf, "OverlayPosition", &self.overlay_pos, true);
         Self::write_field_in(&mut buf, "SkinPreference", &self.skin_preference, true);
         Self::write_field_in(&mut buf, "EpilepsyWarning", &self.epilepsy_warn, true);
         Self::write_field_in(&mut buf, "CountdownOffset", &self.countdown_offset, true);
         Self::write_field_in(&mut buf, "SpecialStyle", &self.special_style, true);
-        Self::write_field_in(&mut buf, "WidescreenStoryboard", &self.widescreen_sb, true);
+        Self::write_field_in_with(
+            &mut buf,
+            "WidescreenStoryboard",
+            &self.widescreen_sb,
+            true,
+            always,
+        );
         Self::write_field_in(
             &mut buf,
             "SamplesMatchPlaybackRate",
@@ -125,11 +183,18 @@ impl ToString for GeneralSection {
     }
 }
 
+impl ToString for GeneralSection {
+    fn to_string(&self) -> String {
+        self.serialize_with(&SerializeOptions::default())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Section;
+    use crate::options::{ParseOptions, SerializeOptions};
     use crate::section::general::GeneralSection;
-    use crate::types::general::Countdown::NONE;
+    use crate::types::general::Countdown::{NONE, NORMAL as NORMAL_COUNTDOWN};
     use crate::types::general::Gamemode::STD;
     use crate::types::general::SampleSet::NORMAL;
 
@@ -155,6 +220,87 @@ WidescreenStoryboard: 1
         assert_eq!(general.widescreen_sb, true.into());
     }
 
+    #[test]
+    fn parse_general_with_decimal_integers() {
+        let section = "AudioFilename: marb.mp3\nAudioLeadIn: 0.0\nPreviewTime: 126478.0\n";
+        let lenient = ParseOptions::new().lenient(true);
+
+        assert!(GeneralSection::parse(section).is_err());
+
+        let general = GeneralSection::parse_with(section, &lenient).unwrap();
+
+        assert_eq!(general.audio_lead_in, 0);
+        assert_eq!(general.preview_time, 126478);
+        assert!(GeneralSection::parse_with("AudioLeadIn: 0.5x", &lenient).is_err());
+    }
+
+    #[test]
+    fn parse_general_duplicate_key() {
+        let general =
+            GeneralSection::parse("PreviewTime: 1000\nAudioFilename: a.mp3\nPreviewTime: 2000")
+                .unwrap();
+
+        assert_eq!(general.preview_time, 2000);
+        assert_eq!(general.audio_filename, "a.mp3");
+    }
+
+    #[test]
+    fn round_trip_textual_bool() {
+        let general =
+            GeneralSection::parse("AudioFilename: a.mp3\nLetterboxInBreaks: true\n").unwrap();
+
+        assert_eq!(general.lb_in_breaks, true.into());
+        assert!(general.lb_in_breaks.is_textual());
+        assert_eq!(
+            general.serialize(),
+            "AudioFilename: a.mp3\nLetterboxInBreaks: true\n"
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn serialize_deprecated_fields() {
+        let general = GeneralSection::parse(
+            "AudioFilename: a.mp3\nAudioHash: abc\nStoryFireInFront: 1\nAlwaysShowPlayfield: 1\n",
+        )
+        .unwrap();
+
+        assert_eq!(general.audio_hash, "abc");
+        assert_eq!(general.story_fire_in_front, true.into());
+        assert_eq!(general.show_playfield, true.into());
+        assert_eq!(general.serialize(), "AudioFilename: a.mp3\n");
+        assert_eq!(
+            general.serialize_with(&SerializeOptions::new().emit_deprecated(true)),
+            "AudioFilename: a.mp3\nAudioHash: abc\nStoryFireInFront: 1\nAlwaysShowPlayfield: 1\n"
+        );
+    }
+
+    #[test]
+    fn parse_general_without_audio() {
+        let general = GeneralSection::parse("AudioLeadIn: 0\nPreviewTime: 2000").unwrap();
+
+        assert_eq!(general.audio_filename, "");
+        assert!(!general.has_audio());
+        assert!(GeneralSection::parse(SECTION_TEST).unwrap().has_audio());
+    }
+
+    #[test]
+    fn osu_default_general() {
+        let general = GeneralSection::osu_default();
+
+        assert_eq!(general.audio_lead_in, 0);
+        assert_eq!(general.preview_time, -1);
+        assert_eq!(general.countdown, NORMAL_COUNTDOWN);
+        assert_eq!(general.sample_set, NORMAL);
+        assert_eq!(general.stack_leniency, 0.7);
+        assert_eq!(general.mode, STD);
+        assert_eq!(general.lb_in_breaks, false.into());
+        assert_eq!(
+            general.serialize(),
+            "PreviewTime: -1\nStackLeniency: 0.7\n".to_string()
+        );
+    }
+
     #[test]
     fn serialize_general() {
         let mut general = GeneralSection::default();
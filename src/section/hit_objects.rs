@@ -1,12 +1,30 @@
 use crate::error::BeatmapParseError;
 use crate::error::BeatmapParseError::InvalidFormat;
-use crate::section::CommaListElement;
+use crate::options::{ParseOptions, SerializeOptions};
+use crate::section::difficulty::DifficultySection;
+use crate::section::timing_points::TimingPoint;
+use crate::section::{CommaListElement, CommaListOf, Timed};
 use crate::types::SampleSet;
+use crate::BeatmapLevel;
 use bitflags::bitflags;
 use regex::Regex;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Sub};
 use std::str::FromStr;
-
-#[derive(Debug, Default, PartialEq, Eq)]
+use std::time::Duration;
+
+/// Width of the playfield in osu! pixels.
+pub const PLAYFIELD_WIDTH: i32 = 512;
+/// Height of the playfield in osu! pixels.
+pub const PLAYFIELD_HEIGHT: i32 = 384;
+/// Distance in osu! pixels objects may be placed outside of the playfield horizontally,
+/// the area still visible on a 16:9 screen.
+const PLAYFIELD_OVERFLOW_X: i32 = 180;
+/// Distance in osu! pixels objects may be placed outside of the playfield vertically.
+const PLAYFIELD_OVERFLOW_Y: i32 = 82;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub struct SliderPoint {
     pub x: i32,
     pub y: i32,
@@ -25,20 +43,66 @@ impl FromStr for SliderPoint {
     }
 }
 
-impl ToString for SliderPoint {
-    fn to_string(&self) -> String {
-        format!("{}:{}", self.x, self.y)
+impl Display for SliderPoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.x, self.y)
+    }
+}
+
+impl From<(i32, i32)> for SliderPoint {
+    fn from((x, y): (i32, i32)) -> Self {
+        SliderPoint { x, y }
+    }
+}
+
+impl From<SliderPoint> for (i32, i32) {
+    fn from(point: SliderPoint) -> Self {
+        (point.x, point.y)
+    }
+}
+
+impl From<SliderPoint> for (f32, f32) {
+    fn from(point: SliderPoint) -> Self {
+        (point.x as f32, point.y as f32)
+    }
+}
+
+impl Add for SliderPoint {
+    type Output = SliderPoint;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        SliderPoint {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+impl Sub for SliderPoint {
+    type Output = SliderPoint;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        SliderPoint {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
     }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
 pub struct EdgeSounds {
     pub sounds: Vec<u32>,
     pub sets: Vec<(u32, u32)>,
 }
 
+impl EdgeSounds {
+    pub fn is_empty(&self) -> bool {
+        self.sounds.is_empty() && self.sets.is_empty()
+    }
+}
+
 impl FromStr for EdgeSounds {
     type Err = ();
 
@@ -67,33 +131,31 @@ impl FromStr for EdgeSounds {
     }
 }
 
-impl ToString for EdgeSounds {
-    fn to_string(&self) -> String {
-        let mut buf = String::new();
-
-        self.sounds.iter().for_each(|sound| {
-            buf.push_str(&sound.to_string());
-            buf.push('|')
-        });
-        buf.pop();
+impl Display for EdgeSounds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, sound) in self.sounds.iter().enumerate() {
+            if i > 0 {
+                f.write_str("|")?;
+            }
+            write!(f, "{}", sound)?;
+        }
 
-        buf.push(',');
+        f.write_str(",")?;
 
-        self.sets.iter().for_each(|set| {
-            buf.push_str(&set.0.to_string());
-            buf.push(':');
-            buf.push_str(&set.1.to_string());
-            buf.push('|');
-        });
-        buf.pop();
+        for (i, set) in self.sets.iter().enumerate() {
+            if i > 0 {
+                f.write_str("|")?;
+            }
+            write!(f, "{}:{}", set.0, set.1)?;
+        }
 
-        buf
+        Ok(())
     }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
 pub enum SliderType {
     Bezier,
     CentripetalCatmullRom,
@@ -136,15 +198,15 @@ impl FromStr for SliderType {
     }
 }
 
-impl ToString for SliderType {
-    fn to_string(&self) -> String {
-        String::from(char::from(self))
+impl Display for SliderType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", char::from(self))
     }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
 pub struct HitSample {
     pub normal_set: SampleSet,
     pub additional_set: SampleSet,
@@ -157,7 +219,14 @@ impl FromStr for HitSample {
     type Err = BeatmapParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s: Vec<&str> = s.trim().split(":").map(|x| x.trim()).collect();
+        // the filename is the remainder after the fourth colon, it may contain colons itself
+        let s: Vec<&str> = s.trim().splitn(5, ':').map(|x| x.trim()).collect();
+
+        if s.len() < 4 {
+            return Err(InvalidFormat {
+                field: "hit_sample".to_string(),
+            });
+        }
 
         Ok(Self {
             normal_set: SampleSet::from_str(s[0]).map_err(|_| InvalidFormat {
@@ -172,27 +241,24 @@ impl FromStr for HitSample {
             volume: u8::from_str(s[3]).map_err(|_| InvalidFormat {
                 field: "volume".to_string(),
             })?,
-            filename: String::from(s[4]),
+            filename: String::from(*s.get(4).unwrap_or(&"")),
         })
     }
 }
 
-impl ToString for HitSample {
-    fn to_string(&self) -> String {
-        format!(
+impl Display for HitSample {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
             "{}:{}:{}:{}:{}",
-            self.normal_set.to_string(),
-            self.additional_set.to_string(),
-            self.index,
-            self.volume,
-            self.filename
+            self.normal_set, self.additional_set, self.index, self.volume, self.filename
         )
     }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default, PartialEq, Hash)]
 pub enum HitObjectType {
     #[default]
     HitCircle,
@@ -206,6 +272,16 @@ impl HitObjectType {
     pub fn try_into_inner<T: TryFrom<Self>>(self) -> Result<T, T::Error> {
         self.try_into()
     }
+
+    /// Name of the hit object type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HitObjectType::HitCircle => "HitCircle",
+            HitObjectType::Slider(_) => "Slider",
+            HitObjectType::Spinner(_) => "Spinner",
+            HitObjectType::ManiaHold(_) => "ManiaHold",
+        }
+    }
 }
 
 impl FromStr for HitObjectType {
@@ -284,6 +360,81 @@ impl Default for HitSoundFlag {
     }
 }
 
+impl HitSoundFlag {
+    /// Build the flags from the hitsounds to play.
+    pub fn from_additions(normal: bool, whistle: bool, finish: bool, clap: bool) -> Self {
+        let mut flags = Self::empty();
+
+        flags.set(Self::NORMAL, normal);
+        flags.set(Self::WHISTLE, whistle);
+        flags.set(Self::FINISH, finish);
+        flags.set(Self::CLAP, clap);
+
+        flags
+    }
+
+    pub fn normal(&self) -> bool {
+        self.contains(Self::NORMAL)
+    }
+    pub fn whistle(&self) -> bool {
+        self.contains(Self::WHISTLE)
+    }
+    pub fn finish(&self) -> bool {
+        self.contains(Self::FINISH)
+    }
+    pub fn clap(&self) -> bool {
+        self.contains(Self::CLAP)
+    }
+
+    pub fn set_normal(&mut self, value: bool) {
+        self.set(Self::NORMAL, value)
+    }
+    pub fn set_whistle(&mut self, value: bool) {
+        self.set(Self::WHISTLE, value)
+    }
+    pub fn set_finish(&mut self, value: bool) {
+        self.set(Self::FINISH, value)
+    }
+    pub fn set_clap(&mut self, value: bool) {
+        self.set(Self::CLAP, value)
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Tolerance in milliseconds under which a tick is considered to be on the end of a slide.
+const TICK_END_TOLERANCE: f64 = 0.001;
+
+/// Timing context needed to compute the duration and the ticks of a slider.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SliderContext {
+    /// Duration of a beat in milliseconds, from the uninherited timing point governing the slider.
+    pub beat_length: f64,
+    /// Slider velocity multiplier from the inherited timing point governing the slider.
+    pub slider_velocity: f64,
+    /// Base slider velocity in hundreds of osu! pixels per beat.
+    pub slider_multiplier: f64,
+    /// Amount of slider ticks per beat.
+    pub slider_tick_rate: f64,
+}
+
+impl SliderContext {
+    /// Build the context from the uninherited timing point, the inherited timing point
+    /// (if any) active at the slider time and the difficulty settings of the beatmap.
+    pub fn new(
+        uninherited: &TimingPoint,
+        inherited: Option<&TimingPoint>,
+        difficulty: &DifficultySection,
+    ) -> Self {
+        Self {
+            beat_length: *uninherited.beat_length,
+            slider_velocity: inherited.map_or(1., |x| x.slider_velocity() as f64),
+            slider_multiplier: difficulty.slider_multiplier as f64,
+            slider_tick_rate: difficulty.slider_tick_rate as f64,
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Extra parameters representing a Slider Hit Object.
@@ -292,21 +443,152 @@ pub struct SliderParams {
     pub slider_type: SliderType,
     pub curve_points: Vec<SliderPoint>,
     pub slides: u32,
-    pub length: f32,
+    /// Length of a single slide in osu! pixels. Editor-saved lengths such as `67.5000025749208`
+    /// need the precision of an `f64` to be written back unchanged.
+    pub length: f64,
+    /// Amount of decimals the length was written with when it is a whole number (e.g. `1` for
+    /// `140.0`), so it is serialized back in the same form. `None` writes the shortest form.
+    pub length_decimals: Option<usize>,
     pub edge_sounds: EdgeSounds,
 }
 
+impl Hash for SliderParams {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.slider_type.hash(state);
+        self.curve_points.hash(state);
+        self.slides.hash(state);
+        self.length.to_bits().hash(state);
+        self.length_decimals.hash(state);
+        self.edge_sounds.hash(state);
+    }
+}
+
 impl SliderParams {
+    /// Distance travelled over all the slides of the slider, in osu! pixels.
+    ///
+    /// Returned as an `f64`, the precision of [SliderParams::length], so editor-saved lengths
+    /// aren't rounded.
+    pub fn total_length(&self) -> f64 {
+        self.length * self.slides as f64
+    }
+
+    /// Duration in milliseconds of a single slide of the slider.
+    /// A null or invalid velocity gives a duration of `0` instead of an infinite one.
+    pub fn span_duration(&self, ctx: &SliderContext) -> f64 {
+        let velocity = ctx.slider_multiplier * 100. * ctx.slider_velocity;
+
+        if !velocity.is_finite() || velocity <= 0. {
+            return 0.;
+        }
+
+        self.length / velocity * ctx.beat_length
+    }
+
+    /// Amount of slider ticks over all the slides of the slider.
+    pub fn tick_count(&self, ctx: &SliderContext) -> usize {
+        self.tick_times(ctx).len()
+    }
+
+    /// Times of the slider ticks over all the slides of the slider,
+    /// in milliseconds from the start of the slider.
+    ///
+    /// Ticks are placed every `beat_length / slider_tick_rate` milliseconds from the start of
+    /// each slide, a tick falling on the exact end of a slide is ignored.
+    pub fn tick_times(&self, ctx: &SliderContext) -> Vec<u32> {
+        let span_duration = self.span_duration(ctx);
+        let tick_interval = ctx.beat_length / ctx.slider_tick_rate;
+
+        if !span_duration.is_finite()
+            || !tick_interval.is_finite()
+            || span_duration <= 0.
+            || tick_interval <= 0.
+        {
+            return Vec::new();
+        }
+
+        let mut span_ticks: Vec<f64> = Vec::new();
+        let mut tick = tick_interval;
+        while tick < span_duration - TICK_END_TOLERANCE {
+            span_ticks.push(tick);
+            tick = tick_interval * (span_ticks.len() + 1) as f64;
+        }
+
+        let mut ticks = Vec::with_capacity(span_ticks.len() * self.slides as usize);
+        for slide in 0..self.slides {
+            let span_start = span_duration * slide as f64;
+
+            // reversed slides go back from the tail, so their ticks are mirrored
+            if slide % 2 == 0 {
+                span_ticks
+                    .iter()
+                    .for_each(|t| ticks.push((span_start + t).round() as u32));
+            } else {
+                span_ticks
+                    .iter()
+                    .rev()
+                    .for_each(|t| ticks.push((span_start + span_duration - t).round() as u32));
+            }
+        }
+
+        ticks
+    }
+
+    pub fn serialize_length(&self) -> String {
+        let mut buf = String::new();
+
+        // Writing into a String can't fail
+        self.write_length(&mut buf).unwrap();
+
+        buf
+    }
+
+    fn write_length(&self, f: &mut impl std::fmt::Write) -> std::fmt::Result {
+        match self.length_decimals {
+            Some(decimals) if self.length.fract() == 0. => {
+                write!(f, "{:.*}", decimals, self.length)
+            }
+            _ => write!(f, "{}", self.length),
+        }
+    }
+
+    /// Split the curve points into Bezier segments.
+    ///
+    /// A point repeated consecutively (a "red anchor") ends a segment and starts the next one,
+    /// so it is the last point of a segment and the first point of the following one.
+    /// The position of the slider head isn't part of the curve points, so it isn't included in
+    /// the first segment.
+    pub fn segments(&self) -> Vec<Vec<SliderPoint>> {
+        let mut segments = Vec::new();
+        let mut segment: Vec<SliderPoint> = Vec::new();
+
+        for point in self.curve_points.iter() {
+            if segment.last() == Some(point) {
+                segments.push(std::mem::take(&mut segment));
+            }
+            segment.push(*point);
+        }
+
+        if !segment.is_empty() {
+            segments.push(segment);
+        }
+
+        segments
+    }
+
     pub fn serialize_curve_points(&self) -> String {
         let mut buf = String::new();
 
-        self.curve_points.iter().for_each(|p| {
-            buf.push('|');
-            buf.push_str(&p.to_string());
-        });
+        // Writing into a String can't fail
+        self.write_curve_points(&mut buf).unwrap();
 
         buf
     }
+
+    fn write_curve_points(&self, f: &mut impl std::fmt::Write) -> std::fmt::Result {
+        self.curve_points
+            .iter()
+            .try_for_each(|p| write!(f, "|{}", p))
+    }
 }
 
 impl TryFrom<HitObjectType> for SliderParams {
@@ -326,6 +608,7 @@ impl FromStr for SliderParams {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let s: Vec<&str> = s.trim().splitn(4, ",").map(|x| x.trim()).collect();
         let type_and_points: Vec<&str> = s[0].split("|").collect();
+        let length = *s.get(2).unwrap_or(&"");
 
         Ok(SliderParams {
             slider_type: SliderType::from_str(type_and_points[0]).map_err(|_| ())?,
@@ -338,32 +621,247 @@ impl FromStr for SliderParams {
 
                 x
             },
-            slides: u32::from_str(s.get(1).unwrap_or(&"")).map_err(|_| ())?,
-            length: f32::from_str(s.get(2).unwrap_or(&"")).map_err(|_| ())?,
+            slides: match u32::from_str(s.get(1).unwrap_or(&"")).map_err(|_| ())? {
+                0 => return Err(()),
+                slides => slides,
+            },
+            length: f64::from_str(length).map_err(|_| ())?,
+            length_decimals: match length.split_once('.') {
+                Some((_, decimals)) if decimals.chars().all(|x| x == '0') => Some(decimals.len()),
+                _ => None,
+            },
             edge_sounds: EdgeSounds::from_str(s.get(3).unwrap_or(&"")).unwrap_or_default(),
         })
     }
 }
 
-impl ToString for SliderParams {
-    fn to_string(&self) -> String {
-        format!(
-            "{}{},{},{},{}",
-            self.slider_type.to_string(),
-            self.serialize_curve_points(),
-            self.slides,
-            self.length,
-            self.edge_sounds.to_string()
-        )
+impl Display for SliderParams {
+    /// Serialize the curve, slides, length and edge sounds of the slider.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.slider_type)?;
+        self.write_curve_points(f)?;
+        write!(f, ",{},", self.slides)?;
+        self.write_length(f)?;
+
+        // Old beatmaps don't write the edge sounds of sliders without hitsounds
+        match self.edge_sounds.is_empty() {
+            true => Ok(()),
+            false => write!(f, ",{}", self.edge_sounds),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Distance in osu! pixels between the points sampled along slider paths by
+/// [BeatmapLevel::positions].
+pub const SLIDER_PATH_SPACING: f64 = 10.;
+/// Maximum amount of steps a Bezier segment or a circle arc is approximated with.
+const PATH_MAX_STEPS: usize = 1000;
+/// Amount of steps each Catmull-Rom segment is approximated with, as in osu!.
+const CATMULL_STEPS: usize = 50;
+/// Maximum distance in osu! pixels between a circle arc and the chords approximating it.
+const CIRCLE_ARC_TOLERANCE: f64 = 0.1;
+
+type PathPoint = (f64, f64);
+
+fn distance(a: PathPoint, b: PathPoint) -> f64 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+fn lerp(a: PathPoint, b: PathPoint, t: f64) -> PathPoint {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+/// Approximate the curve of a slider as a polyline, from its anchor points with the head.
+fn curve_polyline(slider_type: &SliderType, anchors: &[SliderPoint]) -> Vec<PathPoint> {
+    let anchors: Vec<PathPoint> = anchors.iter().map(|p| (p.x as f64, p.y as f64)).collect();
+
+    match slider_type {
+        SliderType::Linear => anchors,
+        SliderType::CentripetalCatmullRom => catmull_polyline(&anchors),
+        // osu! only draws circle arcs through exactly 3 points which aren't aligned
+        SliderType::PerfectCircle if anchors.len() == 3 => {
+            circle_arc_polyline(&anchors).unwrap_or_else(|| bezier_polyline(&anchors))
+        }
+        _ => bezier_polyline(&anchors),
+    }
+}
+
+/// Bezier curve split in segments on its red anchors, anchors repeated consecutively.
+fn bezier_polyline(anchors: &[PathPoint]) -> Vec<PathPoint> {
+    let mut polyline = Vec::new();
+    let mut start = 0;
+
+    for end in 1..=anchors.len() {
+        if end == anchors.len() || anchors[end] == anchors[end - 1] {
+            polyline.extend(bezier_segment(&anchors[start..end]));
+            start = end;
+        }
+    }
+
+    polyline
+}
+
+/// Bezier curve evaluated about every osu! pixel of its control polygon.
+fn bezier_segment(points: &[PathPoint]) -> Vec<PathPoint> {
+    let polygon_length: f64 = points.windows(2).map(|x| distance(x[0], x[1])).sum();
+    let steps = (polygon_length.ceil() as usize).clamp(1, PATH_MAX_STEPS);
+
+    (0..=steps)
+        .map(|step| {
+            // De Casteljau's algorithm
+            let t = step as f64 / steps as f64;
+            let mut points = points.to_vec();
+            for n in (1..points.len()).rev() {
+                for i in 0..n {
+                    points[i] = lerp(points[i], points[i + 1], t);
+                }
+            }
+            points[0]
+        })
+        .collect()
+}
+
+fn catmull_polyline(anchors: &[PathPoint]) -> Vec<PathPoint> {
+    let mut polyline = Vec::with_capacity(anchors.len() * CATMULL_STEPS);
+
+    for i in 0..anchors.len().saturating_sub(1) {
+        let (v2, v3) = (anchors[i], anchors[i + 1]);
+        let v1 = match i {
+            0 => v2,
+            _ => anchors[i - 1],
+        };
+        // The last segment continues in the direction of the last anchor
+        let v4 = match anchors.get(i + 2) {
+            Some(v4) => *v4,
+            None => lerp(v2, v3, 2.),
+        };
+
+        for step in 0..CATMULL_STEPS {
+            let t = step as f64 / CATMULL_STEPS as f64;
+            let at = |a: f64, b: f64, c: f64, d: f64| {
+                0.5 * (2. * b
+                    + (c - a) * t
+                    + (2. * a - 5. * b + 4. * c - d) * t * t
+                    + (3. * b - a - 3. * c + d) * t * t * t)
+            };
+            polyline.push((at(v1.0, v2.0, v3.0, v4.0), at(v1.1, v2.1, v3.1, v4.1)));
+        }
+    }
+    polyline.extend(anchors.last());
+
+    polyline
+}
+
+/// Arc of the circle going through the 3 anchors, from the first one to the last one, or
+/// `None` if they are aligned.
+fn circle_arc_polyline(anchors: &[PathPoint]) -> Option<Vec<PathPoint>> {
+    let (a, b, c) = (anchors[0], anchors[1], anchors[2]);
+    // Twice the signed area of the triangle, positive when the anchors turn counterclockwise
+    let cross = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+    if cross.abs() < 1e-3 {
+        return None;
+    }
+
+    let (a_sq, b_sq, c_sq) = (
+        a.0 * a.0 + a.1 * a.1,
+        b.0 * b.0 + b.1 * b.1,
+        c.0 * c.0 + c.1 * c.1,
+    );
+    let centre = (
+        (a_sq * (b.1 - c.1) + b_sq * (c.1 - a.1) + c_sq * (a.1 - b.1)) / (2. * cross),
+        (a_sq * (c.0 - b.0) + b_sq * (a.0 - c.0) + c_sq * (b.0 - a.0)) / (2. * cross),
+    );
+    let radius = distance(centre, a);
+    let start = (a.1 - centre.1).atan2(a.0 - centre.0);
+    let end = (c.1 - centre.1).atan2(c.0 - centre.0);
+    // The arc goes through the middle anchor, turning the same way as the anchors
+    let (direction, range) = match cross > 0. {
+        true => (1., (end - start).rem_euclid(std::f64::consts::TAU)),
+        false => (-1., (start - end).rem_euclid(std::f64::consts::TAU)),
+    };
+
+    let steps = match 2. * radius <= CIRCLE_ARC_TOLERANCE {
+        true => 1,
+        false => {
+            let step_angle = 2. * (1. - CIRCLE_ARC_TOLERANCE / radius).acos();
+            ((range / step_angle).ceil() as usize).clamp(1, PATH_MAX_STEPS)
+        }
+    };
+
+    Some(
+        (0..=steps)
+            .map(|step| {
+                let angle = start + direction * range * step as f64 / steps as f64;
+                (
+                    centre.0 + radius * angle.cos(),
+                    centre.1 + radius * angle.sin(),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Cut the polyline at the given length, or extend its last segment to reach it, as osu! does
+/// when the length of a slider doesn't match its curve. A length that isn't a finite positive
+/// number keeps the whole polyline.
+fn fit_to_length(mut polyline: Vec<PathPoint>, length: f64) -> Vec<PathPoint> {
+    polyline.dedup();
+    if !length.is_finite() || length <= 0. {
+        return polyline;
+    }
+
+    let mut travelled = 0.;
+    for i in 1..polyline.len() {
+        let segment = distance(polyline[i - 1], polyline[i]);
+
+        if travelled + segment >= length {
+            let end = lerp(polyline[i - 1], polyline[i], (length - travelled) / segment);
+            polyline.truncate(i);
+            polyline.push(end);
+            return polyline;
+        }
+        travelled += segment;
+    }
+
+    if let [.., from, to] = polyline[..] {
+        let segment = distance(from, to);
+        polyline.pop();
+        polyline.push(lerp(from, to, (segment + length - travelled) / segment));
+    }
+
+    polyline
+}
+
+/// Points every `spacing` osu! pixels along the polyline, from its start to its end included.
+fn sample_polyline(polyline: &[PathPoint], spacing: f64) -> Vec<PathPoint> {
+    let mut samples: Vec<PathPoint> = polyline.first().copied().into_iter().collect();
+    let mut travelled = 0.;
+
+    for pair in polyline.windows(2) {
+        let segment = distance(pair[0], pair[1]);
+        let mut next = spacing * samples.len() as f64;
+
+        while next < travelled + segment {
+            samples.push(lerp(pair[0], pair[1], (next - travelled) / segment));
+            next = spacing * samples.len() as f64;
+        }
+        travelled += segment;
+    }
+    if polyline.len() > 1 {
+        samples.extend(polyline.last());
     }
+
+    samples
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Extra parameters representing a Spinner Hit Object.
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
 pub struct SpinnerParams {
-    pub end_time: u32,
+    pub end_time: i32,
 }
 
 impl TryFrom<HitObjectType> for SpinnerParams {
@@ -382,23 +880,23 @@ impl FromStr for SpinnerParams {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(Self {
-            end_time: u32::from_str(s).map_err(|_| ())?,
+            end_time: i32::from_str(s).map_err(|_| ())?,
         })
     }
 }
 
-impl ToString for SpinnerParams {
-    fn to_string(&self) -> String {
-        self.end_time.to_string()
+impl Display for SpinnerParams {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.end_time)
     }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Extra parameters representing a Mania Hold Hit Object.
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
 pub struct ManiaHoldParams {
-    pub end_time: u32,
+    pub end_time: i32,
 }
 
 impl TryFrom<HitObjectType> for ManiaHoldParams {
@@ -417,126 +915,470 @@ impl FromStr for ManiaHoldParams {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(Self {
-            end_time: u32::from_str(s).map_err(|_| ())?,
+            end_time: i32::from_str(s).map_err(|_| ())?,
         })
     }
 }
 
-impl ToString for ManiaHoldParams {
-    fn to_string(&self) -> String {
-        self.end_time.to_string()
+impl Display for ManiaHoldParams {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.end_time)
     }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Representation of an Hit Object.
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default)]
 pub struct HitObject {
     /// X Position in osu! pixels of the object.
     pub x: i32,
     /// Y Position in osu! pixels of the object.
     pub y: i32,
     /// Time when the object is to be hit, in milliseconds from the beginning of the beatmap's audio.
-    pub time: u32,
+    pub time: i32,
     /// Type and extra parameters specific to the object's type.
     pub object_params: HitObjectType,
     /// Whether the object is a new combo or not
     pub new_combo: bool,
     /// How many combo colours to skip if it's a new combo
     pub combo_skip: u8,
+    /// Type byte as read from the source, `None` if the object wasn't parsed.
+    /// It is informative only and ignored when comparing or hashing objects.
+    pub raw_type: Option<u8>,
     /// flags indicating the hitsound applied to the object
     pub hit_sound: HitSoundFlag,
     /// Information about which samples are played when the object is hit.
     pub hit_sample: HitSample,
+    /// Trailing `//` comment of the line, with the whitespace before it, kept when parsing with
+    /// [ParseOptions::keep_comments] and written back after the object.
+    /// It is ignored when comparing or hashing objects.
+    pub comment: Option<String>,
 }
 
-impl FromStr for HitObject {
-    type Err = BeatmapParseError;
+impl PartialEq for HitObject {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x
+            && self.y == other.y
+            && self.time == other.time
+            && self.object_params == other.object_params
+            && self.new_combo == other.new_combo
+            && self.combo_skip == other.combo_skip
+            && self.hit_sound == other.hit_sound
+            && self.hit_sample == other.hit_sample
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let split: Vec<&str> = s.trim().splitn(6, ",").map(|x| x.trim()).collect();
-        let mut hit_object = HitObject::new();
+impl Hash for HitObject {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.x.hash(state);
+        self.y.hash(state);
+        self.time.hash(state);
+        self.object_params.hash(state);
+        self.new_combo.hash(state);
+        self.combo_skip.hash(state);
+        self.hit_sound.hash(state);
+        self.hit_sample.hash(state);
+    }
+}
 
-        let object_type =
-            HitObjectTypeFlag::from_bits_truncate(u8::from_str(split[3]).map_err(|_| {
-                InvalidFormat {
-                    field: "object_type".to_string(),
-                }
-            })?);
+impl HitObject {
+    /// Hit circle at the given position and time.
+    ///
+    /// Objects built by these constructors have no hitsound bit set, as written by the osu!
+    /// editor, and play the normal hitsound.
+    pub fn circle(x: i32, y: i32, time: i32) -> Self {
+        HitObject {
+            x,
+            y,
+            time,
+            object_params: HitObjectType::HitCircle,
+            hit_sound: HitSoundFlag::empty(),
+            ..Default::default()
+        }
+    }
 
-        if object_type.contains(HitObjectTypeFlag::NEW_COMBO) {
-            hit_object.new_combo = true
+    /// Spinner from `time` to `end_time`, at the centre of the playfield and starting a new
+    /// combo as placed by the osu! editor.
+    pub fn spinner(time: i32, end_time: i32) -> Self {
+        HitObject {
+            x: PLAYFIELD_WIDTH / 2,
+            y: PLAYFIELD_HEIGHT / 2,
+            time,
+            object_params: HitObjectType::Spinner(SpinnerParams { end_time }),
+            new_combo: true,
+            hit_sound: HitSoundFlag::empty(),
+            ..Default::default()
         }
+    }
 
-        let mut combo_skip_count = 0u8;
-        if object_type.contains(HitObjectTypeFlag::SKIP_ONE) {
-            combo_skip_count += 1;
+    /// osu!mania hold from `time` to `end_time` in the given column, counted from `0`, of a
+    /// beatmap with `columns` columns. The hold is placed at the centre of its column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `column` isn't lower than `columns`, as the hold would be placed outside of
+    /// the playfield.
+    pub fn mania_hold(column: u32, time: i32, end_time: i32, columns: u32) -> Self {
+        assert!(
+            column < columns,
+            "column {} is out of a beatmap with {} columns",
+            column,
+            columns
+        );
+        // Computed on 64 bits so large columns can't overflow, the result is below the width
+        let x = (2 * column as u64 + 1) * PLAYFIELD_WIDTH as u64 / (2 * columns as u64);
+
+        HitObject {
+            x: x as i32,
+            y: PLAYFIELD_HEIGHT / 2,
+            time,
+            object_params: HitObjectType::ManiaHold(ManiaHoldParams { end_time }),
+            hit_sound: HitSoundFlag::empty(),
+            ..Default::default()
         }
-        if object_type.contains(HitObjectTypeFlag::SKIP_TWO) {
-            combo_skip_count += 2;
+    }
+
+    /// Time when the object is to be hit, as a [Duration] from the beginning of the beatmap's audio.
+    pub fn time_duration(&self) -> Duration {
+        // Durations can't be negative, times before the audio start are clamped to it
+        Duration::from_millis(self.time.max(0) as u64)
+    }
+
+    /// Set the time of the object from a [Duration], truncated to the millisecond.
+    pub fn set_time_from_duration(&mut self, duration: Duration) {
+        self.time = i32::try_from(duration.as_millis()).unwrap_or(i32::MAX);
+    }
+
+    /// Time when the object ends, in milliseconds from the beginning of the beatmap's audio.
+    ///
+    /// The duration of a slider depends on the timing of the beatmap, so this returns the start
+    /// time for sliders. See [BeatmapLevel::object_end_time](crate::BeatmapLevel::object_end_time)
+    /// to take it into account.
+    ///
+    /// An end time before the start time is invalid, the start time is returned instead so the
+    /// duration of the object is never negative.
+    pub fn end_time(&self) -> i32 {
+        match &self.object_params {
+            HitObjectType::Spinner(x) => x.end_time.max(self.time),
+            HitObjectType::ManiaHold(x) => x.end_time.max(self.time),
+            _ => self.time,
         }
-        if object_type.contains(HitObjectTypeFlag::SKIP_FOUR) {
-            combo_skip_count += 4;
+    }
+
+    /// Volume the object is played at: the volume of its hit sample, or when it is `0`, the
+    /// volume of the timing point active at the time of the object in the given beatmap.
+    pub fn effective_volume(&self, beatmap: &BeatmapLevel) -> u8 {
+        match self.hit_sample.volume {
+            0 => beatmap.sample_state_at(self.time).2,
+            volume => volume,
         }
-        hit_object.combo_skip = combo_skip_count;
+    }
 
-        hit_object.x = i32::from_str(split[0]).map_err(|_| InvalidFormat {
-            field: "x".to_string(),
-        })?;
-        hit_object.y = i32::from_str(split[1]).map_err(|_| InvalidFormat {
-            field: "y".to_string(),
-        })?;
-        hit_object.time = u32::from_str(split[2]).map_err(|_| InvalidFormat {
-            field: "time".to_string(),
-        })?;
-        hit_object.object_params =
-            HitObjectType::try_from(object_type).map_err(|_| InvalidFormat {
-                field: "object_params".to_string(),
-            })?;
-        hit_object.hit_sound =
-            HitSoundFlag::from_bits_truncate(u8::from_str(split[4]).map_err(|_| {
-                InvalidFormat {
-                    field: "hit_sound".to_string(),
-                }
-            })?);
+    /// Whether the object is a spinner or a hold ending before it starts.
+    pub fn has_negative_duration(&self) -> bool {
+        match &self.object_params {
+            HitObjectType::Spinner(x) => x.end_time < self.time,
+            HitObjectType::ManiaHold(x) => x.end_time < self.time,
+            _ => false,
+        }
+    }
 
-        match hit_object.object_params {
-            HitObjectType::HitCircle => {
-                let hit_sample = split.get(5);
-                match hit_sample {
-                    Some(hit_sample) => {
-                        hit_object.hit_sample =
-                            HitSample::from_str(hit_sample).map_err(|_| InvalidFormat {
-                                field: "hit_sample".to_string(),
-                            })?;
-                        Ok(hit_object)
-                    }
-                    None => {
-                        hit_object.hit_sample = HitSample::default();
-                        Ok(hit_object)
-                    }
-                }
-            }
-            HitObjectType::Slider(ref mut _params) => {
-                let mut vec_splitted_params: Vec<&str> = split[5].split_inclusive(",").collect();
+    /// Type byte of the object as it is serialized: object type, new combo and combo skip bits.
+    ///
+    /// Compare it with [HitObject::raw_type] to check whether the parsed type is reproduced.
+    pub fn type_byte(&self) -> u8 {
+        let mut type_infos = HitObjectTypeFlag::from(&self.object_params);
 
-                // verify that the last element is the hit sample and remove it if yes
-                let re: Regex = Regex::new(r"([0-9]+:[0-9]+:[0-9]+:[0-9]+:)").unwrap();
-                if re.is_match(vec_splitted_params.last().unwrap_or(&"")) {
-                    vec_splitted_params.pop();
-                }
-                // verify that the last characters is a ',' and remove it if yes
-                let mut string_params: String = vec_splitted_params.drain(0..).collect();
-                if string_params.chars().last().unwrap() == ',' {
-                    string_params.pop();
-                }
+        if self.new_combo {
+            type_infos.insert(HitObjectTypeFlag::NEW_COMBO);
 
-                *_params = SliderParams::from_str(&string_params).map_err(|_| InvalidFormat {
-                    field: "object_params".to_string(),
-                })?;
+            // Skip flags only apply to new combos
+            if self.combo_skip & (1 << 0) != 0 {
+                type_infos.insert(HitObjectTypeFlag::SKIP_ONE);
+            }
+            if self.combo_skip & (1 << 1) != 0 {
+                type_infos.insert(HitObjectTypeFlag::SKIP_TWO);
+            }
+            if self.combo_skip & (1 << 2) != 0 {
+                type_infos.insert(HitObjectTypeFlag::SKIP_FOUR);
+            }
+        }
+
+        type_infos.bits
+    }
+
+    /// Anchor points of a slider starting with its head, the position of the object,
+    /// or `None` if the object isn't a slider.
+    pub fn full_curve(&self) -> Option<Vec<SliderPoint>> {
+        match &self.object_params {
+            HitObjectType::Slider(params) => Some(
+                std::iter::once(SliderPoint {
+                    x: self.x,
+                    y: self.y,
+                })
+                .chain(params.curve_points.iter().copied())
+                .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Path of a slider over its first slide, from its head to its end, as points every
+    /// `spacing` osu! pixels along it, or `None` if the object isn't a slider. The following
+    /// slides go back and forth along the same path.
+    ///
+    /// The curve is evaluated from the anchor points as osu! draws it for the type of the
+    /// slider, then cut at the length of the slider, or its last segment is extended to reach
+    /// it. A length that isn't a finite positive number keeps the whole curve.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `spacing` isn't a finite positive number.
+    pub fn slider_path(&self, spacing: f64) -> Option<Vec<(f64, f64)>> {
+        assert!(
+            spacing.is_finite() && spacing > 0.,
+            "spacing must be a finite positive number, got {}",
+            spacing
+        );
+
+        Some(sample_polyline(&self.slider_polyline()?, spacing))
+    }
+
+    /// Position where a slider ends after all its slides: its head after an even number of
+    /// slides, else the end of its path (see [HitObject::slider_path]). `None` if the object
+    /// isn't a slider.
+    pub fn slider_end_position(&self) -> Option<(f64, f64)> {
+        match &self.object_params {
+            HitObjectType::Slider(params) if params.slides % 2 == 0 => {
+                Some((self.x as f64, self.y as f64))
+            }
+            _ => self.slider_polyline()?.last().copied(),
+        }
+    }
+
+    fn slider_polyline(&self) -> Option<Vec<PathPoint>> {
+        match &self.object_params {
+            HitObjectType::Slider(params) => Some(fit_to_length(
+                curve_polyline(&params.slider_type, &self.full_curve()?),
+                params.length,
+            )),
+            _ => None,
+        }
+    }
 
-                let hit_sample = split.get(6);
+    /// Start a new combo on the object, skipping `skip` combo colours.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `skip` is above 7, as the combo skip is stored in 3 bits.
+    pub fn set_new_combo(&mut self, skip: u8) {
+        assert!(skip <= 7, "combo skip must be at most 7, got {}", skip);
+        self.new_combo = true;
+        self.combo_skip = skip;
+    }
+
+    /// Continue the current combo on the object, the combo skip only applying to new combos.
+    pub fn clear_new_combo(&mut self) {
+        self.new_combo = false;
+        self.combo_skip = 0;
+    }
+
+    /// Whether the object is placed where it can be seen and clicked: in the playfield or in
+    /// the area around it still visible on screen, `[-180, 692]` horizontally and `[-82, 466]`
+    /// vertically. Slider curve points aren't checked.
+    pub fn in_playfield(&self) -> bool {
+        (-PLAYFIELD_OVERFLOW_X..=PLAYFIELD_WIDTH + PLAYFIELD_OVERFLOW_X).contains(&self.x)
+            && (-PLAYFIELD_OVERFLOW_Y..=PLAYFIELD_HEIGHT + PLAYFIELD_OVERFLOW_Y).contains(&self.y)
+    }
+
+    /// Whether the object is placed inside the 512x384 playfield, as the editor grid allows.
+    /// Slider curve points aren't checked.
+    pub fn strictly_in_playfield(&self) -> bool {
+        (0..=PLAYFIELD_WIDTH).contains(&self.x) && (0..=PLAYFIELD_HEIGHT).contains(&self.y)
+    }
+
+    /// Reverse the direction of a slider: its head moves to the last anchor point of its curve
+    /// and the anchor points are walked backwards. The edge sounds are reversed to stay on the
+    /// same edges, while the slides, length and hit sample are kept.
+    ///
+    /// Nothing is done if the object isn't a slider. The slider ends where its curve ends only
+    /// if its length matches the curve, as the osu! editor keeps it.
+    pub fn reverse_slider(&mut self) {
+        let mut curve = match self.full_curve() {
+            Some(curve) => curve,
+            None => return,
+        };
+        curve.reverse();
+
+        if let HitObjectType::Slider(params) = &mut self.object_params {
+            self.x = curve[0].x;
+            self.y = curve[0].y;
+            params.curve_points = curve.split_off(1);
+            params.edge_sounds.sounds.reverse();
+            params.edge_sounds.sets.reverse();
+        }
+    }
+
+    /// Reset the hitsound, the samples and the slider edge sounds of the object to their
+    /// defaults. Edge sounds are zeroed but kept, one for each edge of the slider.
+    pub fn clear_hitsounds(&mut self) {
+        self.hit_sound = HitSoundFlag::default();
+        self.hit_sample = HitSample::default();
+
+        if let HitObjectType::Slider(params) = &mut self.object_params {
+            params.edge_sounds.sounds.iter_mut().for_each(|x| *x = 0);
+            params.edge_sounds.sets.iter_mut().for_each(|x| *x = (0, 0));
+        }
+    }
+
+    /// Mirror the object and its slider curve points across the vertical axis of the playfield
+    /// (`x -> 512 - x`).
+    pub fn mirror_horizontal(&mut self) {
+        self.x = PLAYFIELD_WIDTH - self.x;
+
+        if let HitObjectType::Slider(params) = &mut self.object_params {
+            params
+                .curve_points
+                .iter_mut()
+                .for_each(|p| p.x = PLAYFIELD_WIDTH - p.x);
+        }
+    }
+
+    /// Mirror the object and its slider curve points across the horizontal axis of the playfield
+    /// (`y -> 384 - y`).
+    pub fn mirror_vertical(&mut self) {
+        self.y = PLAYFIELD_HEIGHT - self.y;
+
+        if let HitObjectType::Slider(params) = &mut self.object_params {
+            params
+                .curve_points
+                .iter_mut()
+                .for_each(|p| p.y = PLAYFIELD_HEIGHT - p.y);
+        }
+    }
+}
+
+impl FromStr for HitObject {
+    type Err = BeatmapParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, &ParseOptions::default())
+    }
+}
+
+/// Split a line on the start of its trailing `//` comment, outside of any quoted content.
+fn split_comment(s: &str) -> (&str, Option<&str>) {
+    let mut in_quotes = false;
+
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '/' if !in_quotes && s[i..].starts_with("//") => {
+                let start = s[..i].trim_end().len();
+                return (&s[..start], Some(&s[start..]));
+            }
+            _ => (),
+        }
+    }
+
+    (s, None)
+}
+
+impl HitObject {
+    /// Parse a hit object with the given options.
+    ///
+    /// In lenient mode, a trailing `//` comment is removed from the line before parsing it,
+    /// and kept in [HitObject::comment] if [ParseOptions::keep_comments] is set.
+    pub fn parse_with(s: &str, options: &ParseOptions) -> Result<Self, BeatmapParseError> {
+        let (s, comment) = match options.lenient {
+            true => split_comment(s.trim()),
+            false => (s, None),
+        };
+
+        let mut hit_object = Self::parse_fields(s)?;
+        if options.keep_comments {
+            hit_object.comment = comment.map(|x| x.to_string());
+        }
+
+        Ok(hit_object)
+    }
+
+    /// Serialize the hit object with the given options.
+    /// Only [SerializeOptions::omit_default_hit_sample] changes how hit objects are written.
+    pub fn serialize_with(&self, options: &SerializeOptions) -> String {
+        let omit_hit_sample = options.omit_default_hit_sample
+            && self.object_params == HitObjectType::HitCircle
+            && self.hit_sample == HitSample::default();
+
+        let mut buf = String::new();
+
+        // Writing into a String can't fail
+        self.write_fields(&mut buf, omit_hit_sample).unwrap();
+        buf.push_str(self.comment.as_deref().unwrap_or_default());
+
+        buf
+    }
+
+    fn parse_fields(s: &str) -> Result<Self, BeatmapParseError> {
+        let split: Vec<&str> = s.trim().splitn(6, ",").map(|x| x.trim()).collect();
+        let mut hit_object = HitObject::new();
+
+        // Every object has at least its position, time, type and hitsound, and objects other
+        // than circles also need their params
+        let too_few_fields = || InvalidFormat {
+            field: "hit_object".to_string(),
+        };
+        if split.len() < 5 {
+            return Err(too_few_fields());
+        }
+
+        let raw_type = u8::from_str(split[3]).map_err(|_| InvalidFormat {
+            field: "object_type".to_string(),
+        })?;
+        let object_type = HitObjectTypeFlag::from_bits_truncate(raw_type);
+        hit_object.raw_type = Some(raw_type);
+
+        if object_type.contains(HitObjectTypeFlag::NEW_COMBO) {
+            hit_object.new_combo = true
+        }
+
+        let mut combo_skip_count = 0u8;
+        if object_type.contains(HitObjectTypeFlag::SKIP_ONE) {
+            combo_skip_count += 1;
+        }
+        if object_type.contains(HitObjectTypeFlag::SKIP_TWO) {
+            combo_skip_count += 2;
+        }
+        if object_type.contains(HitObjectTypeFlag::SKIP_FOUR) {
+            combo_skip_count += 4;
+        }
+        hit_object.combo_skip = combo_skip_count;
+
+        hit_object.x = i32::from_str(split[0]).map_err(|_| InvalidFormat {
+            field: "x".to_string(),
+        })?;
+        hit_object.y = i32::from_str(split[1]).map_err(|_| InvalidFormat {
+            field: "y".to_string(),
+        })?;
+        hit_object.time = i32::from_str(split[2]).map_err(|_| InvalidFormat {
+            field: "time".to_string(),
+        })?;
+        hit_object.object_params =
+            HitObjectType::try_from(object_type).map_err(|_| InvalidFormat {
+                field: "object_params".to_string(),
+            })?;
+        hit_object.hit_sound =
+            HitSoundFlag::from_bits_truncate(u8::from_str(split[4]).map_err(|_| {
+                InvalidFormat {
+                    field: "hit_sound".to_string(),
+                }
+            })?);
+
+        match hit_object.object_params {
+            HitObjectType::HitCircle => {
+                // The hit sample may be left empty after the hitsound
+                let hit_sample = split.get(5).filter(|x| !x.is_empty());
                 match hit_sample {
                     Some(hit_sample) => {
                         hit_object.hit_sample =
@@ -551,8 +1393,42 @@ impl FromStr for HitObject {
                     }
                 }
             }
+            HitObjectType::Slider(ref mut _params) => {
+                let mut vec_splitted_params: Vec<&str> = split
+                    .get(5)
+                    .ok_or_else(too_few_fields)?
+                    .split_inclusive(",")
+                    .collect();
+
+                // verify that the last element is the hit sample and remove it if yes
+                let re: Regex = Regex::new(r"([0-9]+:[0-9]+:[0-9]+:[0-9]+:)").unwrap();
+                let mut hit_sample = None;
+                if re.is_match(vec_splitted_params.last().unwrap_or(&"")) {
+                    hit_sample = vec_splitted_params.pop();
+                }
+                // verify that the last characters is a ',' and remove it if yes
+                let mut string_params: String = vec_splitted_params.drain(0..).collect();
+                if string_params.ends_with(',') {
+                    string_params.pop();
+                }
+
+                *_params = SliderParams::from_str(&string_params).map_err(|_| InvalidFormat {
+                    field: "object_params".to_string(),
+                })?;
+
+                hit_object.hit_sample = match hit_sample {
+                    Some(hit_sample) => {
+                        HitSample::from_str(hit_sample).map_err(|_| InvalidFormat {
+                            field: "hit_sample".to_string(),
+                        })?
+                    }
+                    None => HitSample::default(),
+                };
+                Ok(hit_object)
+            }
             HitObjectType::Spinner(ref mut _params) => {
-                let splitted = split[5].split_once(",").ok_or_else(|| InvalidFormat {
+                let params = split.get(5).ok_or_else(too_few_fields)?;
+                let splitted = params.split_once(",").ok_or_else(|| InvalidFormat {
                     field: "object_params/hit_sample".to_string(),
                 })?;
 
@@ -564,7 +1440,8 @@ impl FromStr for HitObject {
                 Ok(hit_object)
             }
             HitObjectType::ManiaHold(ref mut _params) => {
-                let splitted = split[5].split_once(":").ok_or_else(|| InvalidFormat {
+                let params = split.get(5).ok_or_else(too_few_fields)?;
+                let splitted = params.split_once(":").ok_or_else(|| InvalidFormat {
                     field: "object_params/hit_sample".to_string(),
                 })?;
 
@@ -579,52 +1456,83 @@ impl FromStr for HitObject {
     }
 }
 
-impl ToString for HitObject {
-    fn to_string(&self) -> String {
-        let mut type_infos = HitObjectTypeFlag::from(&self.object_params);
-
-        if self.new_combo {
-            type_infos.insert(HitObjectTypeFlag::NEW_COMBO);
-        }
-        if self.combo_skip & (1 << 0) == 1 {
-            type_infos.insert(HitObjectTypeFlag::SKIP_ONE);
-        }
-        if self.combo_skip & (1 << 1) == 1 {
-            type_infos.insert(HitObjectTypeFlag::SKIP_TWO);
-        }
-        if self.combo_skip & (1 << 2) == 1 {
-            type_infos.insert(HitObjectTypeFlag::SKIP_FOUR);
-        }
-
-        let mut buf = format!(
+impl HitObject {
+    /// Write the fields of the hit object, ending with its hit sample unless `omit_hit_sample`.
+    fn write_fields(
+        &self,
+        f: &mut impl std::fmt::Write,
+        omit_hit_sample: bool,
+    ) -> std::fmt::Result {
+        write!(
+            f,
             "{},{},{},{},{},",
-            self.x, self.y, self.time, type_infos.bits, self.hit_sound.bits
-        );
+            self.x,
+            self.y,
+            self.time,
+            self.type_byte(),
+            self.hit_sound.bits
+        )?;
 
         match &self.object_params {
-            HitObjectType::Slider(x) => {
-                buf.push_str(&x.to_string());
-                buf.push(',');
-            }
-            HitObjectType::Spinner(x) => {
-                buf.push_str(&x.to_string());
-                buf.push(',');
-            }
-            HitObjectType::ManiaHold(x) => {
-                buf.push_str(&x.to_string());
-                buf.push(',');
-            }
+            HitObjectType::Slider(x) => match (x.edge_sounds.is_empty(), &self.hit_sample) {
+                // Old beatmaps end sliders without hitsounds after their length
+                (true, hit_sample) if *hit_sample == HitSample::default() => {
+                    return write!(f, "{}", x)
+                }
+                (true, _) => write!(f, "{},,,", x)?,
+                (false, _) => write!(f, "{},", x)?,
+            },
+            HitObjectType::Spinner(x) => write!(f, "{},", x)?,
+            // The end time of holds is the first field of their hit sample
+            HitObjectType::ManiaHold(x) => write!(f, "{}:", x)?,
             _ => (),
         }
 
-        buf.push_str(&self.hit_sample.to_string());
+        match omit_hit_sample {
+            true => Ok(()),
+            false => write!(f, "{}", self.hit_sample),
+        }
+    }
+}
 
-        buf
+impl Display for HitObject {
+    /// Serialize the hit object as a line of the `[HitObjects]` section.
+    ///
+    /// Sliders without edge sounds are written as old beatmaps write them: the line ends after
+    /// the length when the hit sample is the default one, otherwise empty edge sounds and edge
+    /// sets (`,,,`) are written before the hit sample.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.write_fields(f, false)?;
+
+        match &self.comment {
+            Some(comment) => write!(f, "{}", comment),
+            None => Ok(()),
+        }
     }
 }
 
 impl CommaListElement for HitObject {}
 
+impl CommaListOf<HitObject> {
+    /// Parse the hit objects section with the given options, see [HitObject::parse_with].
+    pub fn parse_with(s: &str, options: &ParseOptions) -> Result<Self, BeatmapParseError> {
+        Self::parse_elements(s, |x| HitObject::parse_with(x, options))
+    }
+
+    /// Serialize the hit objects section with the given options, see [HitObject::serialize_with].
+    pub fn serialize_with(&self, options: &SerializeOptions) -> String {
+        self.iter()
+            .map(|x| format!("{}\n", x.serialize_with(options)))
+            .collect()
+    }
+}
+
+impl Timed for HitObject {
+    fn time(&self) -> i32 {
+        self.time
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::section::hit_objects::*;
@@ -646,6 +1554,7 @@ mod tests {
             ],
             slider_type: SliderType::Bezier,
             length: 310.123,
+            length_decimals: None,
             slides: 2,
             edge_sounds: EdgeSounds {
                 sounds: vec![2, 1, 2],
@@ -659,6 +1568,8 @@ mod tests {
             object_params: object_type,
             new_combo: true,
             combo_skip: 0,
+            raw_type: Some(6),
+            comment: None,
             hit_sound: HitSoundFlag::default(),
             hit_sample: HitSample::default(),
         }
@@ -671,6 +1582,8 @@ mod tests {
             object_params: HitObjectType::Spinner(SpinnerParams { end_time: 12000 }),
             new_combo: false,
             combo_skip: 0,
+            raw_type: Some(8),
+            comment: None,
             hit_sound: HitSoundFlag::FINISH | HitSoundFlag::CLAP,
             hit_sample: HitSample::default(),
         };
@@ -686,6 +1599,8 @@ mod tests {
             object_params: HitObjectType::HitCircle,
             new_combo: true,
             combo_skip: 1,
+            raw_type: Some(21),
+            comment: None,
             hit_sound: HitSoundFlag::WHISTLE,
             hit_sample: HitSample::default(),
         }
@@ -709,6 +1624,235 @@ mod tests {
         assert_eq!(hit_objects.serialize(), TEST_SECTION)
     }
 
+    #[test]
+    fn type_byte_matches_raw_type() {
+        let section = format!("{}{}", TEST_SECTION, "64,64,13000,101,0,0:0:0:0:\n");
+        let hit_objects: CommaListOf<HitObject> = CommaListOf::parse(&section).unwrap();
+
+        assert_eq!(hit_objects[3].combo_skip, 6);
+        for hit_object in hit_objects.iter() {
+            assert_eq!(Some(hit_object.type_byte()), hit_object.raw_type);
+        }
+        assert_eq!(hit_objects.serialize(), section);
+    }
+
+    #[test]
+    fn slider_point_arithmetic() {
+        let head = SliderPoint::from((100, 200));
+        let tail: SliderPoint = (160, 120).into();
+
+        assert_eq!(head, SliderPoint { x: 100, y: 200 });
+        assert_eq!(tail - head, SliderPoint { x: 60, y: -80 });
+        assert_eq!(head + (tail - head), tail);
+        assert_eq!(<(i32, i32)>::from(tail), (160, 120));
+
+        let (x, y): (f32, f32) = (tail - head).into();
+        assert_eq!(x.hypot(y), 100.);
+    }
+
+    #[test]
+    fn effective_volume() {
+        let mut beatmap_level = BeatmapLevel::new();
+        beatmap_level.timing_points = "1000,500,4,1,0,80,1,0\n2000,-100,4,1,0,50,0,0"
+            .parse()
+            .unwrap();
+
+        let circle = test_circle_object();
+        assert_eq!(circle.hit_sample.volume, 0);
+        assert_eq!(circle.effective_volume(&beatmap_level), 50);
+        assert_eq!(test_spinner_object().effective_volume(&beatmap_level), 80);
+    }
+
+    #[test]
+    fn constructors() {
+        let circle = HitObject::circle(100, 200, 1000);
+        assert_eq!(circle.serialize(), "100,200,1000,1,0,0:0:0:0:");
+
+        let spinner = HitObject::spinner(2000, 4000);
+        assert_eq!(spinner.serialize(), "256,192,2000,12,0,4000,0:0:0:0:");
+
+        let hold = HitObject::mania_hold(1, 5000, 5500, 4);
+        assert_eq!(hold.serialize(), "192,192,5000,128,0,5500:0:0:0:0:");
+        assert_eq!(HitObject::parse(&hold.serialize()).unwrap(), hold);
+        assert_eq!(HitObject::mania_hold(6, 0, 100, 7).x, 475);
+        assert_eq!(HitObject::mania_hold(u32::MAX - 1, 0, 100, u32::MAX).x, 511);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mania_hold_out_of_columns() {
+        HitObject::mania_hold(10, 5000, 5500, 4);
+    }
+
+    #[test]
+    fn slider_path() {
+        let path = |s: &str, spacing: f64| HitObject::parse(s).unwrap().slider_path(spacing);
+
+        let linear = path("0,0,0,2,0,L|100:0,1,50", 10.).unwrap();
+        assert_eq!(linear.len(), 6);
+        assert_eq!(linear[1], (10., 0.));
+        assert_eq!(linear[5], (50., 0.));
+        // The last segment is extended to reach the length
+        assert_eq!(path("0,0,0,2,0,L|30:0,1,50", 10.).unwrap()[5], (50., 0.));
+        assert_eq!(path("0,0,0,2,0,L|30:0,1,50", 20.).unwrap().len(), 4);
+
+        // Points sampled along the half circle centred on (50, 0) with a radius of 50, up to the
+        // tolerance of the arc approximation
+        let circle = path("0,0,0,2,0,P|50:50|100:0,1,157.08", 10.).unwrap();
+        assert!(circle
+            .iter()
+            .all(|x| (distance(*x, (50., 0.)) - 50.).abs() <= 0.2));
+        assert!(distance(circle[circle.len() - 1], (100., 0.)) <= 0.2);
+        assert!(circle.iter().any(|x| x.1 > 49.9));
+
+        // The curve goes halfway towards its control point
+        let bezier = path("0,0,0,2,0,B|50:100|100:0,1,1000", 1.).unwrap();
+        let apex = bezier.iter().map(|x| x.1).fold(0., f64::max);
+        assert!((apex - 50.).abs() < 0.01);
+
+        // Red anchors split the curve in straight segments
+        let red_anchors = path("0,0,0,2,0,B|100:0|100:0|100:100,1,200", 100.).unwrap();
+        assert_eq!(red_anchors, vec![(0., 0.), (100., 0.), (100., 100.)]);
+
+        // Catmull-Rom curves go through their anchors
+        let catmull = path("0,0,0,2,0,C|50:50|100:0,1,1000", 1.).unwrap();
+        assert!(catmull.iter().any(|x| distance(*x, (50., 50.)) < 1.));
+
+        assert_eq!(path("0,0,0,1,0,0:0:0:0:", 10.), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn slider_path_invalid_spacing() {
+        test_slider_object().slider_path(0.);
+    }
+
+    #[test]
+    fn slider_end_position() {
+        let end = |s: &str| HitObject::parse(s).unwrap().slider_end_position();
+
+        assert_eq!(end("0,0,0,2,0,L|100:0,1,50"), Some((50., 0.)));
+        assert_eq!(end("0,0,0,2,0,L|100:0,3,50"), Some((50., 0.)));
+        assert_eq!(end("0,0,0,2,0,L|100:0,2,50"), Some((0., 0.)));
+        assert_eq!(end("0,0,0,1,0,0:0:0:0:"), None);
+    }
+
+    #[test]
+    fn reverse_slider() {
+        let mut slider = test_slider_object();
+
+        slider.reverse_slider();
+        assert_eq!((slider.x, slider.y), (300, 150));
+        assert_eq!(
+            slider.serialize(),
+            "300,150,12600,6,1,B|250:200|250:200|200:200|100:100,2,310.123,2|1|2,0:2|0:0|0:0,0:0:0:0:"
+        );
+
+        slider.reverse_slider();
+        assert_eq!(slider, test_slider_object());
+    }
+
+    #[test]
+    fn in_playfield() {
+        let mut circle = test_circle_object();
+        assert!(circle.in_playfield());
+        assert!(circle.strictly_in_playfield());
+
+        circle.x = -100;
+        assert!(circle.in_playfield());
+        assert!(!circle.strictly_in_playfield());
+
+        circle.x = 256;
+        circle.y = 500;
+        assert!(!circle.in_playfield());
+        assert!(!circle.strictly_in_playfield());
+    }
+
+    #[test]
+    fn set_new_combo() {
+        let mut spinner = test_spinner_object();
+
+        spinner.set_new_combo(3);
+        assert_eq!(spinner.type_byte(), 8 | 4 | 16 | 32);
+        assert!(spinner.serialize().starts_with("256,192,11200,60,"));
+
+        spinner.clear_new_combo();
+        assert_eq!(spinner.type_byte(), 8);
+        assert_eq!(spinner.combo_skip, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_new_combo_invalid_skip() {
+        test_spinner_object().set_new_combo(8);
+    }
+
+    #[test]
+    fn write_hit_objects() {
+        let hit_objects: CommaListOf<HitObject> = CommaListOf::parse(TEST_SECTION).unwrap();
+        let mut buf = Vec::new();
+
+        hit_objects.write_to(&mut buf).unwrap();
+
+        assert_eq!(String::from_utf8(buf).unwrap(), TEST_SECTION);
+    }
+
+    mod hit_sample {
+        use super::*;
+
+        #[test]
+        fn parse_hit_sample() {
+            let hit_sample = HitSample::from_str("1:2:3:80:soft-hitclap2.wav").unwrap();
+
+            assert_eq!(hit_sample.normal_set, SampleSet::Normal);
+            assert_eq!(hit_sample.additional_set, SampleSet::Soft);
+            assert_eq!(hit_sample.index, 3);
+            assert_eq!(hit_sample.volume, 80);
+            assert_eq!(hit_sample.filename, "soft-hitclap2.wav");
+        }
+
+        #[test]
+        fn parse_hit_sample_empty_filename() {
+            let hit_sample = HitSample::from_str("0:0:0:0:").unwrap();
+
+            assert_eq!(hit_sample, HitSample::default());
+        }
+
+        #[test]
+        fn parse_hit_sample_without_filename() {
+            let hit_sample = HitSample::from_str("1:2:0:70").unwrap();
+
+            assert_eq!(hit_sample.normal_set, SampleSet::Normal);
+            assert_eq!(hit_sample.additional_set, SampleSet::Soft);
+            assert_eq!(hit_sample.volume, 70);
+            assert_eq!(hit_sample.filename, "");
+        }
+
+        #[test]
+        fn parse_truncated_hit_sample() {
+            assert_eq!(
+                HitSample::from_str("0:0"),
+                Err(InvalidFormat {
+                    field: "hit_sample".to_string()
+                })
+            );
+        }
+
+        #[test]
+        fn parse_invalid_hit_sample() {
+            assert!(HitSample::from_str("a:0:0:0:").is_err());
+            assert!(HitSample::from_str("9:0:0:0:").is_err());
+        }
+
+        #[test]
+        fn parse_hit_sample_filename_with_colon() {
+            let hit_sample = HitSample::from_str("0:0:0:0:C:\\samples\\clap.wav").unwrap();
+
+            assert_eq!(hit_sample.filename, "C:\\samples\\clap.wav");
+            assert_eq!(hit_sample.to_string(), "0:0:0:0:C:\\samples\\clap.wav");
+        }
+    }
+
     mod hit_object {
         use super::*;
 
@@ -748,6 +1892,71 @@ mod tests {
             assert_eq!(spinner.hit_sample.volume, 80);
         }
 
+        #[test]
+        fn parse_hit_object_with_comment() {
+            let line = "256,192,11000,5,0 // stack";
+            let options = ParseOptions::new().lenient(true);
+
+            assert!(HitObject::parse(line).is_err());
+            let circle = HitObject::parse_with(line, &options).unwrap();
+            assert_eq!(circle, HitObject::parse("256,192,11000,5,0").unwrap());
+            assert_eq!(circle.comment, None);
+            assert_eq!(circle.serialize(), "256,192,11000,5,0,0:0:0:0:");
+
+            let circle = HitObject::parse_with(line, &options.keep_comments(true)).unwrap();
+            assert_eq!(circle.comment.as_deref(), Some(" // stack"));
+            assert_eq!(circle.serialize(), "256,192,11000,5,0,0:0:0:0: // stack");
+        }
+
+        #[test]
+        fn serialize_omitting_default_hit_sample() {
+            let options = SerializeOptions::new().omit_default_hit_sample(true);
+            let circle = HitObject::parse("256,192,11000,1,2,").unwrap();
+
+            assert_eq!(circle.serialize(), "256,192,11000,1,2,0:0:0:0:");
+            assert_eq!(circle.serialize_with(&options), "256,192,11000,1,2,");
+
+            let circle = HitObject::parse("256,192,11000,1,2,0:0:0:40:").unwrap();
+            assert_eq!(
+                circle.serialize_with(&options),
+                "256,192,11000,1,2,0:0:0:40:"
+            );
+            let spinner = test_spinner_object();
+            assert_eq!(spinner.serialize_with(&options), spinner.serialize());
+        }
+
+        #[test]
+        fn parse_truncated_hit_object() {
+            let too_few_fields = Err(InvalidFormat {
+                field: "hit_object".to_string(),
+            });
+
+            assert_eq!(HitObject::parse("256,192,11000"), too_few_fields);
+            assert_eq!(HitObject::parse("256,192,11000,1"), too_few_fields);
+            assert_eq!(HitObject::parse("256,192,11200,12,12"), too_few_fields);
+            assert!(HitObject::parse("256,192,11000,1,0").is_ok());
+        }
+
+        #[test]
+        fn round_trip_new_combo_spinner() {
+            let line = "256,192,11200,12,12,12000,3:0:0:80:";
+            let spinner = HitObject::parse(line).unwrap();
+
+            assert_eq!(
+                spinner.object_params,
+                HitObjectType::Spinner(SpinnerParams { end_time: 12000 })
+            );
+            assert!(spinner.new_combo);
+            assert_eq!(spinner.combo_skip, 0);
+            assert_eq!(spinner.hit_sound, HitSoundFlag::FINISH | HitSoundFlag::CLAP);
+            assert_eq!(spinner.type_byte(), 12);
+            assert_eq!(spinner.serialize(), line);
+
+            let mut spinner = test_spinner_object();
+            spinner.new_combo = true;
+            assert_eq!(HitObject::parse(&spinner.serialize()).unwrap(), spinner);
+        }
+
         #[test]
         fn parse_slider() {
             let slider = HitObject::parse(TEST_SLIDER).unwrap();
@@ -768,6 +1977,226 @@ mod tests {
             assert_eq!(slider_params.edge_sounds.sets.len(), 3);
         }
 
+        #[test]
+        fn round_trip_slider_hit_sample() {
+            let line = "300,200,1432,38,0,P|350:250|400:200,2,140,0|8|0,0:0|1:0|0:0,1:2:0:0:";
+            let slider = HitObject::parse(line).unwrap();
+
+            assert_eq!(slider.combo_skip, 2);
+            assert_eq!(slider.hit_sample.normal_set, SampleSet::Normal);
+            assert_eq!(slider.serialize(), line);
+        }
+
+        #[test]
+        fn parse_slider_hit_sample() {
+            let line = "300,200,1432,6,0,L|350:250,1,140,0|0,0:0|0:0,2:3:4:70:hit.wav";
+            let slider = HitObject::parse(line).unwrap();
+
+            assert_eq!(slider.hit_sample.normal_set, SampleSet::Soft);
+            assert_eq!(slider.hit_sample.additional_set, SampleSet::Drum);
+            assert_eq!(slider.hit_sample.index, 4);
+            assert_eq!(slider.hit_sample.volume, 70);
+            assert_eq!(slider.hit_sample.filename, "hit.wav");
+
+            let slider = HitObject::parse("300,200,1432,6,0,L|350:250,1,140,0|0,0:0|0:0").unwrap();
+
+            assert_eq!(slider.hit_sample, HitSample::default());
+        }
+
+        #[test]
+        fn round_trip_slider_without_edge_sounds() {
+            let line = "227,258,52192,6,0,L|247:321,2,50";
+            let mut slider = HitObject::parse(line).unwrap();
+
+            assert_eq!(slider.serialize(), line);
+
+            slider.hit_sample.volume = 50;
+
+            assert_eq!(
+                slider.serialize(),
+                "227,258,52192,6,0,L|247:321,2,50,,,0:0:0:50:"
+            );
+            assert_eq!(
+                HitObject::parse(&slider.serialize())
+                    .unwrap()
+                    .hit_sample
+                    .volume,
+                50
+            );
+        }
+
+        #[test]
+        fn serialize_slider_edge_sounds() {
+            let mut slider =
+                HitObject::parse("227,258,52192,6,0,L|247:321,2,50,2|0|0,0:0|0:0|0:0,0:0:0:0:")
+                    .unwrap();
+
+            assert_eq!(
+                slider.serialize(),
+                "227,258,52192,6,0,L|247:321,2,50,2|0|0,0:0|0:0|0:0,0:0:0:0:"
+            );
+
+            if let HitObjectType::Slider(ref mut params) = slider.object_params {
+                params.edge_sounds = EdgeSounds::default();
+            }
+
+            assert_eq!(slider.serialize(), "227,258,52192,6,0,L|247:321,2,50");
+
+            slider.hit_sample.index = 3;
+
+            assert_eq!(
+                slider.serialize(),
+                "227,258,52192,6,0,L|247:321,2,50,,,0:0:3:0:"
+            );
+        }
+
+        #[test]
+        fn slider_full_curve() {
+            let slider = HitObject::parse(TEST_SLIDER).unwrap();
+            let full_curve = slider.full_curve().unwrap();
+
+            assert_eq!(full_curve.len(), 5);
+            assert_eq!(full_curve[0], SliderPoint { x: 100, y: 100 });
+            assert_eq!(full_curve[4], SliderPoint { x: 300, y: 150 });
+            assert_eq!(
+                HitObject::parse(TEST_HIT_CIRCLE).unwrap().full_curve(),
+                None
+            );
+        }
+
+        #[test]
+        fn slider_ticks() {
+            let slider_params = SliderParams {
+                length: 300.,
+                slides: 2,
+                ..Default::default()
+            };
+            let ctx = SliderContext {
+                beat_length: 500.,
+                slider_velocity: 1.,
+                slider_multiplier: 1.5,
+                slider_tick_rate: 2.,
+            };
+
+            assert_eq!(slider_params.span_duration(&ctx), 1000.);
+            assert_eq!(slider_params.tick_count(&ctx), 6);
+            assert_eq!(
+                slider_params.tick_times(&ctx),
+                vec![250, 500, 750, 1250, 1500, 1750]
+            );
+        }
+
+        #[test]
+        fn slider_ticks_ignore_end() {
+            let slider_params = SliderParams {
+                length: 150.,
+                slides: 1,
+                ..Default::default()
+            };
+            let ctx = SliderContext {
+                beat_length: 500.,
+                slider_velocity: 1.,
+                slider_multiplier: 1.5,
+                slider_tick_rate: 1.,
+            };
+
+            assert_eq!(slider_params.tick_count(&ctx), 0);
+        }
+
+        #[test]
+        fn slider_null_multiplier() {
+            let slider_params = SliderParams {
+                length: 150.,
+                slides: 1,
+                ..Default::default()
+            };
+            let ctx = SliderContext {
+                beat_length: 500.,
+                slider_velocity: 1.,
+                slider_multiplier: 0.,
+                slider_tick_rate: 0.,
+            };
+
+            assert_eq!(slider_params.span_duration(&ctx), 0.);
+            assert_eq!(slider_params.tick_count(&ctx), 0);
+        }
+
+        #[test]
+        fn toggle_hit_sounds() {
+            let mut hit_circle = HitObject::parse(TEST_HIT_CIRCLE).unwrap();
+
+            assert!(hit_circle.hit_sound.whistle());
+            assert!(!hit_circle.hit_sound.clap());
+
+            hit_circle.hit_sound.set_whistle(false);
+            hit_circle.hit_sound.set_finish(true);
+            hit_circle.hit_sound.set_clap(true);
+
+            assert_eq!(hit_circle.hit_sound.bits(), 12);
+            assert_eq!(
+                hit_circle.hit_sound,
+                HitSoundFlag::from_additions(false, false, true, true)
+            );
+            assert_eq!(hit_circle.serialize(), "256,192,11000,21,12,0:0:0:0:");
+        }
+
+        #[test]
+        fn mirror_slider() {
+            let mut slider = test_slider_object();
+            slider.mirror_horizontal();
+            let slider_params: SliderParams = slider.object_params.try_into_inner().unwrap();
+
+            assert_eq!((slider.x, slider.y), (412, 100));
+            assert_eq!(
+                slider_params.curve_points[0],
+                SliderPoint { x: 312, y: 200 }
+            );
+
+            let mut slider = test_slider_object();
+            slider.mirror_vertical();
+            let slider_params: SliderParams = slider.object_params.try_into_inner().unwrap();
+
+            assert_eq!((slider.x, slider.y), (100, 284));
+            assert_eq!(
+                slider_params.curve_points[3],
+                SliderPoint { x: 300, y: 234 }
+            );
+        }
+
+        #[test]
+        fn slider_total_length() {
+            let slider_params: SliderParams =
+                test_slider_object().object_params.try_into_inner().unwrap();
+
+            assert_eq!(slider_params.total_length(), 620.246);
+
+            let slider_params = SliderParams::from_str("L|200:100,2,67.5000025749208").unwrap();
+            assert_eq!(slider_params.total_length(), 135.0000051498416);
+        }
+
+        #[test]
+        fn round_trip_slider_whole_length() {
+            let slider = "100,100,12600,2,0,L|200:100,1,140,2|0,0:0|0:0,0:0:0:0:";
+            let slider_decimal = "100,100,12600,2,0,L|200:100,1,140.0,2|0,0:0|0:0,0:0:0:0:";
+
+            assert_eq!(HitObject::parse(slider).unwrap().serialize(), slider);
+            assert_eq!(
+                HitObject::parse(slider_decimal).unwrap().serialize(),
+                slider_decimal
+            );
+        }
+
+        #[test]
+        fn parse_slider_zero_slides() {
+            assert_eq!(
+                HitObject::parse("100,100,12600,2,0,L|200:100,0,140,2|0,0:0|0:0,0:0:0:0:")
+                    .unwrap_err(),
+                InvalidFormat {
+                    field: "object_params".to_string()
+                }
+            );
+        }
+
         #[test]
         fn serialize_hit_circle() {
             assert_eq!(test_circle_object().serialize(), TEST_HIT_CIRCLE)
@@ -782,5 +2211,60 @@ mod tests {
         fn serialize_slider() {
             assert_eq!(test_slider_object().serialize(), TEST_SLIDER)
         }
+
+        #[test]
+        fn slider_segments() {
+            let slider = HitObject::parse(TEST_SLIDER).unwrap();
+            let slider_params: SliderParams = slider.object_params.try_into_inner().unwrap();
+
+            assert_eq!(slider_params.curve_points.len(), 4);
+            assert_eq!(
+                slider_params.segments(),
+                vec![
+                    vec![
+                        SliderPoint { x: 200, y: 200 },
+                        SliderPoint { x: 250, y: 200 }
+                    ],
+                    vec![
+                        SliderPoint { x: 250, y: 200 },
+                        SliderPoint { x: 300, y: 150 }
+                    ],
+                ]
+            );
+            assert_eq!(
+                slider_params.serialize_curve_points(),
+                "|200:200|250:200|250:200|300:150"
+            );
+        }
+
+        #[test]
+        fn reversed_spinner_end_time() {
+            let spinner = HitObject::parse("256,192,12000,12,0,11200,0:0:0:0:").unwrap();
+
+            assert!(spinner.has_negative_duration());
+            assert_eq!(spinner.end_time(), 12000);
+            assert!(!test_spinner_object().has_negative_duration());
+        }
+
+        #[test]
+        fn parse_negative_time_spinner() {
+            let spinner = HitObject::parse("256,192,-500,12,0,-100,0:0:0:0:").unwrap();
+
+            assert_eq!(spinner.time, -500);
+            assert_eq!(spinner.end_time(), -100);
+            assert_eq!(spinner.serialize(), "256,192,-500,12,0,-100,0:0:0:0:");
+        }
+
+        #[test]
+        fn time_duration() {
+            let mut hit_object = test_circle_object();
+
+            assert_eq!(hit_object.time_duration(), Duration::from_millis(11000));
+
+            hit_object.set_time_from_duration(Duration::from_micros(12_345_678));
+
+            assert_eq!(hit_object.time, 12345);
+            assert_eq!(hit_object.time_duration(), Duration::from_millis(12345));
+        }
     }
 }
@@ -1,10 +1,11 @@
 use crate::error::BeatmapParseError;
-use crate::section::{Section, SectionKeyValue};
+use crate::options::{ParseOptions, SerializeOptions};
+use crate::section::{KeyValueLines, Section, SectionKeyValue};
 use std::str::FromStr;
 
 /// [Information](https://osu.ppy.sh/wiki/en/Client/Beatmap_editor/Song_Setup#song-and-map-metadata)
 /// used to identify the beatmap
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq, Eq, Hash)]
 pub struct MetadataSection {
     /// Romanised song title
     pub title: String,
@@ -36,7 +37,14 @@ impl FromStr for MetadataSection {
     type Err = BeatmapParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s: Vec<&str> = s.trim().split("\n").map(|x| x.trim()).collect();
+        Self::parse_with(s, &ParseOptions::default())
+    }
+}
+
+impl MetadataSection {
+    /// Parse the section with the given options.
+    pub fn parse_with(s: &str, options: &ParseOptions) -> Result<Self, BeatmapParseError> {
+        let s = KeyValueLines::new(s, options);
         let mut metadata = Self::new();
 
         metadata.title = Self::get_field_name_value(&s, "Title")?;
@@ -48,41 +56,96 @@ impl FromStr for MetadataSection {
         metadata.source = Self::get_field_name_value(&s, "Source")?;
 
         let tags: String = Self::get_field_name_value(&s, "Tags")?;
-        metadata.tags = tags.split(' ').map(|x| x.to_string()).collect();
+        metadata.tags = parse_tags(&tags);
 
         metadata.beatmap_id = Self::get_field_name_value(&s, "BeatmapID")?;
         metadata.beatmap_set_id = Self::get_field_name_value(&s, "BeatmapSetID")?;
 
         Ok(metadata)
     }
-}
 
-impl ToString for MetadataSection {
-    fn to_string(&self) -> String {
+    /// Serialize the section with the given options.
+    pub fn serialize_with(&self, options: &SerializeOptions) -> String {
         let mut buf = String::new();
-        let mut tags = String::new();
+        let always = options.editor_fields;
+        let tags = serialize_tags(&self.tags);
+
+        Self::write_field_in_with(&mut buf, "Title", &self.title, false, always);
+        Self::write_field_in_with(&mut buf, "TitleUnicode", &self.title_unicode, false, always);
+        Self::write_field_in_with(&mut buf, "Artist", &self.artist, false, always);
+        Self::write_field_in_with(
+            &mut buf,
+            "ArtistUnicode",
+            &self.artist_unicode,
+            false,
+            always,
+        );
+        Self::write_field_in_with(&mut buf, "Creator", &self.creator, false, always);
+        Self::write_field_in_with(&mut buf, "Version", &self.version, false, always);
+        Self::write_field_in_with(&mut buf, "Source", &self.source, false, always);
+        Self::write_field_in_with(&mut buf, "Tags", &tags, false, always);
+        Self::write_field_in_with(&mut buf, "BeatmapID", &self.beatmap_id, false, always);
+        Self::write_field_in_with(
+            &mut buf,
+            "BeatmapSetID",
+            &self.beatmap_set_id,
+            false,
+            always,
+        );
 
-        for tag in self.tags.iter() {
-            tags.push_str(tag);
-            tags.push(' ')
+        buf
+    }
+}
+
+/// Split the tags on spaces, keeping a quoted tag such as `"drum and bass"` as a single
+/// tag without its quotes. Repeated spaces don't produce empty tags.
+///
+/// The tags are slices of the source, so [BeatmapView](crate::view::BeatmapView) splits them
+/// the same way without allocating.
+pub(crate) fn split_tags(s: &str) -> impl Iterator<Item = &str> {
+    let mut rest = s;
+
+    std::iter::from_fn(move || loop {
+        rest = rest.trim_start_matches(' ');
+        if rest.is_empty() {
+            return None;
         }
 
-        if tags.chars().count() > 0 {
-            tags.pop();
+        let (tag, remaining) = match rest.strip_prefix('"') {
+            // An unclosed quote runs to the end of the tags
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => rest.split_at(rest.find([' ', '"']).unwrap_or(rest.len())),
+        };
+        rest = remaining;
+
+        if !tag.is_empty() {
+            return Some(tag);
         }
+    })
+}
 
-        Self::write_field_in(&mut buf, "Title", &self.title, false);
-        Self::write_field_in(&mut buf, "TitleUnicode", &self.title_unicode, false);
-        Self::write_field_in(&mut buf, "Artist", &self.artist, false);
-        Self::write_field_in(&mut buf, "ArtistUnicode", &self.artist_unicode, false);
-        Self::write_field_in(&mut buf, "Creator", &self.creator, false);
-        Self::write_field_in(&mut buf, "Version", &self.version, false);
-        Self::write_field_in(&mut buf, "Source", &self.source, false);
-        Self::write_field_in(&mut buf, "Tags", &tags, false);
-        Self::write_field_in(&mut buf, "BeatmapID", &self.beatmap_id, false);
-        Self::write_field_in(&mut buf, "BeatmapSetID", &self.beatmap_set_id, false);
+fn parse_tags(s: &str) -> Vec<String> {
+    split_tags(s).map(String::from).collect()
+}
 
-        buf
+/// Join the tags with spaces, quoting the tags containing a space.
+fn serialize_tags(tags: &[String]) -> String {
+    tags.iter()
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| {
+            if tag.contains(' ') {
+                format!("\"{}\"", tag)
+            } else {
+                tag.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+impl ToString for MetadataSection {
+    fn to_string(&self) -> String {
+        self.serialize_with(&SerializeOptions::default())
     }
 }
 
@@ -118,6 +181,35 @@ BeatmapSetID:387784
         assert_eq!(metadata.beatmap_set_id, 387784)
     }
 
+    #[test]
+    fn parse_metadata_exact_keys() {
+        let metadata =
+            MetadataSection::from_str("Title:Romanised\nTitleUnicode:Unicode\nArtist:A").unwrap();
+
+        assert_eq!(metadata.title, "Romanised");
+        assert_eq!(metadata.title_unicode, "Unicode");
+        assert_eq!(metadata.artist, "A");
+        assert_eq!(metadata.artist_unicode, "");
+    }
+
+    #[test]
+    fn parse_quoted_tags() {
+        let metadata =
+            MetadataSection::from_str("Tags:dnb  \"drum and bass\" liquid \"\"").unwrap();
+
+        assert_eq!(metadata.tags, vec!["dnb", "drum and bass", "liquid"]);
+        assert_eq!(metadata.serialize(), "Tags:dnb \"drum and bass\" liquid\n");
+    }
+
+    #[test]
+    fn parse_empty_tags() {
+        let metadata = MetadataSection::from_str("Title:Marble Soda\nTags:").unwrap();
+        assert!(metadata.tags.is_empty());
+
+        let metadata = MetadataSection::from_str("Title:Marble Soda").unwrap();
+        assert!(metadata.tags.is_empty());
+    }
+
     #[test]
     fn serialize_metadata() {
         let mut metadata = MetadataSection::new();
@@ -5,13 +5,111 @@ pub mod events;
 pub mod general;
 pub mod hit_objects;
 pub mod metadata;
+pub mod storyboard;
 pub mod timing_points;
 
 use crate::error::BeatmapParseError;
-use std::fmt::{Debug, Display};
+use crate::options::ParseOptions;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{self, Write};
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
 
+/// Identifier of a section handled by the library.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SectionId {
+    General,
+    Editor,
+    Metadata,
+    Difficulty,
+    Events,
+    TimingPoints,
+    Colours,
+    HitObjects,
+}
+
+impl SectionId {
+    /// Every section, in the order they are written in a beatmap file.
+    pub const ALL: [SectionId; 8] = [
+        SectionId::General,
+        SectionId::Editor,
+        SectionId::Metadata,
+        SectionId::Difficulty,
+        SectionId::Events,
+        SectionId::TimingPoints,
+        SectionId::Colours,
+        SectionId::HitObjects,
+    ];
+
+    /// Name of the section as written in its header.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SectionId::General => "General",
+            SectionId::Editor => "Editor",
+            SectionId::Metadata => "Metadata",
+            SectionId::Difficulty => "Difficulty",
+            SectionId::Events => "Events",
+            SectionId::TimingPoints => "TimingPoints",
+            SectionId::Colours => "Colours",
+            SectionId::HitObjects => "HitObjects",
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Split a beatmap file into its sections, yielding the name and the trimmed content of each
+/// section in file order. Section headers are only recognized at the start of a line.
+///
+/// The sections are located lazily, so the file is only scanned as far as the sections are
+/// consumed.
+pub(crate) fn split_sections(s: &str) -> Sections<'_> {
+    Sections {
+        s,
+        lines: s.split_inclusive('\n'),
+        current: None,
+        offset: 0,
+    }
+}
+
+/// Iterator over the sections of a beatmap file, see [split_sections].
+pub(crate) struct Sections<'a> {
+    s: &'a str,
+    lines: std::str::SplitInclusive<'a, char>,
+    /// Name of the section being read and offset of its content.
+    current: Option<(&'a str, usize)>,
+    offset: usize,
+}
+
+impl<'a> Iterator for Sections<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for line in self.lines.by_ref() {
+            let trimmed = line.trim();
+            let line_start = self.offset;
+            self.offset += line.len();
+
+            if trimmed.len() > 1 && trimmed.starts_with('[') && trimmed.ends_with(']') {
+                let previous = self
+                    .current
+                    .replace((&trimmed[1..trimmed.len() - 1], self.offset));
+
+                if let Some((name, start)) = previous {
+                    return Some((name, self.s[start..line_start].trim()));
+                }
+            }
+        }
+
+        self.current
+            .take()
+            .map(|(name, start)| (name, self.s[start..].trim()))
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
 /// Trait representing a section in an osu file format.
 trait Section: Debug + Default + FromStr<Err = BeatmapParseError> + ToString {
     fn new() -> Self {
@@ -28,14 +126,45 @@ trait Section: Debug + Default + FromStr<Err = BeatmapParseError> + ToString {
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// Trimmed lines of a section in the format of `key:value` pairs, read by [SectionKeyValue].
+pub(crate) struct KeyValueLines<'a> {
+    lines: Vec<&'a str>,
+    /// Whether keys written with another casing are accepted, see [ParseOptions::lenient].
+    lenient: bool,
+}
+
+impl<'a> KeyValueLines<'a> {
+    pub(crate) fn new(s: &'a str, options: &ParseOptions) -> Self {
+        Self {
+            lines: s.trim().split('\n').map(|x| x.trim()).collect(),
+            lenient: options.lenient,
+        }
+    }
+}
+
 /// Trait representing a section in the format of `key:value` or `key: value` pairs.
 trait SectionKeyValue: Section {
-    fn get_field_name_value<T>(str: &Vec<&str>, field_name: &str) -> Result<T, BeatmapParseError>
+    fn get_field_name_value<T>(
+        str: &KeyValueLines,
+        field_name: &str,
+    ) -> Result<T, BeatmapParseError>
     where
         T: FromStr + Default,
     {
-        match str.iter().find(|x| x.contains(field_name)) {
-            Some(pair) => Ok(Self::read_value(*pair)
+        Self::get_field_name_value_or(str, field_name, T::default())
+    }
+
+    /// Same as [SectionKeyValue::get_field_name_value], returning `default` if the field is absent.
+    fn get_field_name_value_or<T>(
+        str: &KeyValueLines,
+        field_name: &str,
+        default: T,
+    ) -> Result<T, BeatmapParseError>
+    where
+        T: FromStr,
+    {
+        match Self::find_field(str, field_name) {
+            Some(pair) => Ok(Self::read_value(pair)
                 .map_err(|_| BeatmapParseError::InvalidFormat {
                     field: field_name.into(),
                 })?
@@ -43,10 +172,97 @@ trait SectionKeyValue: Section {
                 .map_err(|_| BeatmapParseError::InvalidFormat {
                     field: field_name.into(),
                 })?),
-            None => Ok(T::default()),
+            None => Ok(default),
+        }
+    }
+
+    /// Same as [SectionKeyValue::get_field_name_value_or] for a decimal field.
+    /// If `lenient` is set, a single comma used as the decimal separator (`1,5`) is accepted.
+    fn get_decimal_field_value_or<T>(
+        str: &KeyValueLines,
+        field_name: &str,
+        default: T,
+        lenient: bool,
+    ) -> Result<T, BeatmapParseError>
+    where
+        T: FromStr,
+    {
+        Self::get_normalized_field_value_or(str, field_name, default, |value| {
+            match lenient && value.matches(',').count() == 1 && !value.contains('.') {
+                true => value.replace(',', "."),
+                false => value,
+            }
+        })
+    }
+
+    /// Same as [SectionKeyValue::get_field_name_value_or] for an integer field.
+    /// If `lenient` is set, a decimal value (`126478.0`) is accepted and truncated.
+    fn get_integer_field_value_or<T>(
+        str: &KeyValueLines,
+        field_name: &str,
+        default: T,
+        lenient: bool,
+    ) -> Result<T, BeatmapParseError>
+    where
+        T: FromStr,
+    {
+        Self::get_normalized_field_value_or(str, field_name, default, |value| {
+            match value.split_once('.') {
+                Some((integer, decimals))
+                    if lenient && decimals.chars().all(|x| x.is_ascii_digit()) =>
+                {
+                    integer.to_string()
+                }
+                _ => value,
+            }
+        })
+    }
+
+    /// Same as [SectionKeyValue::get_field_name_value_or], passing the value through `normalize`
+    /// before parsing it.
+    fn get_normalized_field_value_or<T>(
+        str: &KeyValueLines,
+        field_name: &str,
+        default: T,
+        normalize: impl FnOnce(String) -> String,
+    ) -> Result<T, BeatmapParseError>
+    where
+        T: FromStr,
+    {
+        let invalid_format = || BeatmapParseError::InvalidFormat {
+            field: field_name.into(),
+        };
+
+        match Self::find_field(str, field_name) {
+            Some(pair) => normalize(Self::read_value(pair).map_err(|_| invalid_format())?)
+                .parse()
+                .map_err(|_| invalid_format()),
+            None => Ok(default),
         }
     }
 
+    /// Find the `key:value` pair of a field.
+    /// In lenient mode, a key written with another casing, such as in hand-edited files, is used
+    /// if the field isn't written with its exact key.
+    fn find_field<'a>(str: &KeyValueLines<'a>, field_name: &str) -> Option<&'a str> {
+        let find = |matches: &dyn Fn(&str) -> bool| {
+            // osu! keeps the last occurrence of a key defined multiple times
+            str.lines
+                .iter()
+                .rev()
+                .find(|x| match x.split_once(':') {
+                    Some((key, _)) => matches(key.trim()),
+                    None => false,
+                })
+                .copied()
+        };
+
+        find(&|key| key == field_name).or_else(|| match str.lenient {
+            true => find(&|key| key.eq_ignore_ascii_case(field_name)),
+            false => None,
+        })
+    }
+
     fn serialize_field<T>(field_name: &str, value: &T, with_space: bool) -> Option<String>
     where
         T: Display + Default + PartialEq,
@@ -71,6 +287,26 @@ trait SectionKeyValue: Section {
         }
     }
 
+    /// Same as [SectionKeyValue::write_field_in], also writing the field when it has its default
+    /// value if `always` is set.
+    fn write_field_in_with<T>(
+        buf: &mut String,
+        field_name: &str,
+        value: &T,
+        with_space: bool,
+        always: bool,
+    ) where
+        T: Display + Default + PartialEq,
+    {
+        match always {
+            true => match with_space {
+                true => buf.push_str(&format!("{}: {}\n", field_name, value)),
+                false => buf.push_str(&format!("{}:{}\n", field_name, value)),
+            },
+            false => Self::write_field_in(buf, field_name, value, with_space),
+        }
+    }
+
     fn read_value(pair: &str) -> Result<String, BeatmapParseError> {
         let value: &str = match pair.split_once(':') {
             Some(x) => x.1.trim(),
@@ -83,8 +319,92 @@ trait SectionKeyValue: Section {
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
+/// Key and separator of a `key:value` field as written in a beatmap file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyStyle {
+    /// Key as written, with its original casing.
+    pub key: String,
+    /// Whether the key is followed by `: ` rather than `:`.
+    pub with_space: bool,
+}
+
+/// Style of the keys of the `key:value` sections of a parsed beatmap, reused when serializing
+/// it so hand-edited files keep their casing and spacing. Fields without a style are written
+/// with the keys and separators of osu!.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyStyles {
+    styles: HashMap<(SectionId, String), Vec<KeyStyle>>,
+    /// Whether the beatmap was parsed in lenient mode, reading keys written in another casing.
+    lenient: bool,
+}
+
+impl KeyStyles {
+    pub(crate) fn new(options: &ParseOptions) -> Self {
+        Self {
+            styles: HashMap::new(),
+            lenient: options.lenient,
+        }
+    }
+
+    /// Style of a field of a section, taken from the line its value is read from: the last one
+    /// written with the exact key, or in lenient mode, else the last one written with the key in
+    /// another casing.
+    ///
+    /// A key in another casing isn't read in strict mode, as in osu!, so its style isn't used:
+    /// the field is written back with the key of osu! to be read again.
+    pub fn get(&self, section: SectionId, key: &str) -> Option<&KeyStyle> {
+        let styles = self.styles.get(&(section, key.to_ascii_lowercase()))?;
+
+        styles
+            .iter()
+            .rev()
+            .find(|x| x.key == key)
+            .or_else(|| styles.last().filter(|_| self.lenient))
+    }
+
+    /// Record the style of every field of the content of a section.
+    pub(crate) fn capture(&mut self, section: SectionId, content: &str) {
+        for line in content.lines().map(|x| x.trim()) {
+            if let Some((key, value)) = line.split_once(':') {
+                let key = key.trim();
+                self.styles
+                    .entry((section, key.to_ascii_lowercase()))
+                    .or_default()
+                    .push(KeyStyle {
+                        key: key.to_string(),
+                        with_space: value.starts_with(' '),
+                    });
+            }
+        }
+    }
+
+    /// Rewrite the fields of the serialized content of a section with their recorded style.
+    pub(crate) fn apply(&self, section: SectionId, content: String) -> String {
+        if self.styles.is_empty() {
+            return content;
+        }
+
+        content
+            .lines()
+            .map(|line| {
+                let restyled = line.split_once(':').and_then(|(key, value)| {
+                    let style = self.get(section, key)?;
+                    let value = value.strip_prefix(' ').unwrap_or(value);
+                    Some(match style.with_space {
+                        true => format!("{}: {}\n", style.key, value),
+                        false => format!("{}:{}\n", style.key, value),
+                    })
+                });
+                restyled.unwrap_or_else(|| format!("{}\n", line))
+            })
+            .collect()
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
 /// Trait representing an element of a section stored as a comma-separated list.
-pub trait CommaListElement: Debug + Default + FromStr<Err = BeatmapParseError> + ToString {
+pub trait CommaListElement: Debug + Default + FromStr<Err = BeatmapParseError> + Display {
     fn new() -> Self {
         Self::default()
     }
@@ -97,10 +417,16 @@ pub trait CommaListElement: Debug + Default + FromStr<Err = BeatmapParseError> +
     }
 }
 
+/// Trait representing an element of a time-ordered section.
+pub trait Timed {
+    /// Time of the element, in milliseconds from the beginning of the beatmap's audio.
+    fn time(&self) -> i32;
+}
+
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Represent a Section under the format of a comma-separated list.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct CommaListOf<T: CommaListElement>(Vec<T>);
 
 impl<T: CommaListElement> Deref for CommaListOf<T> {
@@ -127,11 +453,21 @@ impl<T: CommaListElement> FromStr for CommaListOf<T> {
     type Err = BeatmapParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_elements(s, T::parse)
+    }
+}
+
+impl<T: CommaListElement> CommaListOf<T> {
+    /// Parse each line of the section with `parse`, skipping comments and storyboard entries.
+    pub(crate) fn parse_elements(
+        s: &str,
+        parse: impl Fn(&str) -> Result<T, BeatmapParseError>,
+    ) -> Result<Self, BeatmapParseError> {
         let mut list: Vec<T> = Vec::new();
 
         let s: Vec<&str> = s.trim().split("\n").map(|x| x.trim()).collect();
-        for element in s {
-            let res = T::parse(element);
+        for element in s.into_iter().filter(|x| !x.is_empty()) {
+            let res = parse(element);
             match res {
                 Ok(x) => list.push(x),
                 Err(BeatmapParseError::CommentaryEntry) => (),
@@ -144,16 +480,26 @@ impl<T: CommaListElement> FromStr for CommaListOf<T> {
     }
 }
 
-impl<T: CommaListElement> ToString for CommaListOf<T> {
-    fn to_string(&self) -> String {
-        let mut buf = String::new();
+impl<T: CommaListElement> CommaListOf<T> {
+    /// Serialize the list directly into a writer, one element per line,
+    /// without allocating a string for each element.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.0.iter().try_for_each(|x| writeln!(writer, "{}", x))
+    }
+}
 
-        self.0.iter().for_each(|x| {
-            buf.push_str(&x.serialize());
-            buf.push_str("\n")
-        });
+impl<T: CommaListElement + Timed> CommaListOf<T> {
+    /// Insert an element, keeping a list sorted by time sorted.
+    /// Elements at the same time are kept in insertion order.
+    pub fn insert_sorted(&mut self, element: T) {
+        let index = self.0.partition_point(|x| x.time() <= element.time());
+        self.0.insert(index, element);
+    }
+}
 
-        buf
+impl<T: CommaListElement> Display for CommaListOf<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|x| writeln!(f, "{}", x))
     }
 }
 
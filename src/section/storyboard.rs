@@ -0,0 +1,240 @@
+use crate::error::BeatmapParseError;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Layer on which a storyboard object is drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoryboardLayer {
+    Background,
+    Fail,
+    Pass,
+    Foreground,
+    Overlay,
+}
+
+impl StoryboardLayer {
+    pub const ALL: [StoryboardLayer; 5] = [
+        StoryboardLayer::Background,
+        StoryboardLayer::Fail,
+        StoryboardLayer::Pass,
+        StoryboardLayer::Foreground,
+        StoryboardLayer::Overlay,
+    ];
+
+    /// Comment written by the osu! editor before the objects of the layer.
+    pub fn header(&self) -> &'static str {
+        match self {
+            StoryboardLayer::Background => "//Storyboard Layer 0 (Background)",
+            StoryboardLayer::Fail => "//Storyboard Layer 1 (Fail)",
+            StoryboardLayer::Pass => "//Storyboard Layer 2 (Pass)",
+            StoryboardLayer::Foreground => "//Storyboard Layer 3 (Foreground)",
+            StoryboardLayer::Overlay => "//Storyboard Layer 4 (Overlay)",
+        }
+    }
+}
+
+impl FromStr for StoryboardLayer {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Background" | "0" => Ok(StoryboardLayer::Background),
+            "Fail" | "1" => Ok(StoryboardLayer::Fail),
+            "Pass" | "2" => Ok(StoryboardLayer::Pass),
+            "Foreground" | "3" => Ok(StoryboardLayer::Foreground),
+            "Overlay" | "4" => Ok(StoryboardLayer::Overlay),
+            _ => Err(()),
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+/// Storyboard entries of the `[Events]` section, kept as raw lines.
+///
+/// Objects (sprites and animations) are grouped by the layer they declare, each followed by the
+/// indented lines of its commands, so they can be written back under the right layer header.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Storyboard {
+    pub background: Vec<String>,
+    pub fail: Vec<String>,
+    pub pass: Vec<String>,
+    pub foreground: Vec<String>,
+    pub overlay: Vec<String>,
+    /// `Sample` entries played during the beatmap.
+    pub sound_samples: Vec<String>,
+    /// Whether the section was written by an osu! version predating the Overlay layer, which
+    /// doesn't write its header. The header is still written when the layer has objects.
+    pub without_overlay_header: bool,
+}
+
+impl Storyboard {
+    pub fn is_empty(&self) -> bool {
+        StoryboardLayer::ALL
+            .iter()
+            .all(|x| self.layer(x).is_empty())
+            && self.sound_samples.is_empty()
+    }
+
+    /// Raw lines of the objects of a layer, with their commands.
+    pub fn layer(&self, layer: &StoryboardLayer) -> &Vec<String> {
+        match layer {
+            StoryboardLayer::Background => &self.background,
+            StoryboardLayer::Fail => &self.fail,
+            StoryboardLayer::Pass => &self.pass,
+            StoryboardLayer::Foreground => &self.foreground,
+            StoryboardLayer::Overlay => &self.overlay,
+        }
+    }
+
+    fn layer_mut(&mut self, layer: &StoryboardLayer) -> &mut Vec<String> {
+        match layer {
+            StoryboardLayer::Background => &mut self.background,
+            StoryboardLayer::Fail => &mut self.fail,
+            StoryboardLayer::Pass => &mut self.pass,
+            StoryboardLayer::Foreground => &mut self.foreground,
+            StoryboardLayer::Overlay => &mut self.overlay,
+        }
+    }
+}
+
+impl FromStr for Storyboard {
+    type Err = BeatmapParseError;
+
+    /// Collect the storyboard entries of an `[Events]` section, ignoring the other events.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut storyboard = Storyboard::default();
+        // Layer of the last object, which the following command lines belong to.
+        let mut current: Option<StoryboardLayer> = None;
+
+        storyboard.without_overlay_header = s.contains(StoryboardLayer::Foreground.header())
+            && !s.contains(StoryboardLayer::Overlay.header());
+
+        for line in s.lines().map(|x| x.trim_end()) {
+            if line.starts_with(' ') || line.starts_with('_') {
+                if let Some(layer) = current {
+                    storyboard.layer_mut(&layer).push(line.to_string());
+                }
+                continue;
+            }
+
+            current = None;
+
+            if line.starts_with("Sprite") || line.starts_with("Animation") {
+                let layer = line
+                    .split(',')
+                    .nth(1)
+                    .and_then(|x| StoryboardLayer::from_str(x.trim()).ok())
+                    .unwrap_or(StoryboardLayer::Background);
+                storyboard.layer_mut(&layer).push(line.to_string());
+                current = Some(layer);
+            } else if line.starts_with("Sample") {
+                storyboard.sound_samples.push(line.to_string());
+            }
+        }
+
+        Ok(storyboard)
+    }
+}
+
+impl Display for Storyboard {
+    /// Serialize the storyboard under the layer headers written by the osu! editor.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for layer in StoryboardLayer::ALL.iter() {
+            if *layer == StoryboardLayer::Overlay
+                && self.without_overlay_header
+                && self.overlay.is_empty()
+            {
+                continue;
+            }
+
+            writeln!(f, "{}", layer.header())?;
+            self.layer(layer)
+                .iter()
+                .try_for_each(|x| writeln!(f, "{}", x))?;
+        }
+
+        writeln!(f, "//Storyboard Sound Samples")?;
+        self.sound_samples
+            .iter()
+            .try_for_each(|x| writeln!(f, "{}", x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::section::storyboard::Storyboard;
+
+    const TEST_SECTION: &str = "//Background and Video events
+0,0,\"bg.jpg\",0,0
+//Break Periods
+2,104177,114656
+//Storyboard Layer 0 (Background)
+Sprite,Background,Centre,\"sb/bg.png\",320,240
+ F,0,1000,2000,0,1
+//Storyboard Layer 1 (Fail)
+//Storyboard Layer 2 (Pass)
+//Storyboard Layer 3 (Foreground)
+Sprite,Foreground,Centre,\"sb/light.png\",320,240
+ C,0,0,,142,142,255
+ L,1000,2
+  F,0,0,500,1,0
+Animation,Foreground,Centre,\"sb/anim.png\",320,240,4,100,LoopForever
+ M,0,1000,2000,0,0,320,240
+//Storyboard Layer 4 (Overlay)
+//Storyboard Sound Samples
+Sample,5000,0,\"sb/hit.wav\",70
+";
+
+    #[test]
+    fn parse_storyboard() {
+        let storyboard: Storyboard = TEST_SECTION.parse().unwrap();
+
+        assert_eq!(storyboard.background.len(), 2);
+        assert_eq!(storyboard.foreground.len(), 6);
+        assert!(storyboard.fail.is_empty());
+        assert_eq!(
+            storyboard.sound_samples,
+            vec!["Sample,5000,0,\"sb/hit.wav\",70"]
+        );
+    }
+
+    #[test]
+    fn serialize_storyboard() {
+        let storyboard: Storyboard = TEST_SECTION.parse().unwrap();
+        let storyboard_start = TEST_SECTION.find("//Storyboard Layer 0").unwrap();
+
+        assert_eq!(storyboard.to_string(), &TEST_SECTION[storyboard_start..]);
+    }
+
+    #[test]
+    fn round_trip_without_overlay_header() {
+        let section = "//Storyboard Layer 0 (Background)
+//Storyboard Layer 1 (Fail)
+//Storyboard Layer 2 (Pass)
+//Storyboard Layer 3 (Foreground)
+//Storyboard Sound Samples
+";
+        let storyboard: Storyboard = section.parse().unwrap();
+
+        assert!(storyboard.without_overlay_header);
+        assert_eq!(storyboard.to_string(), section);
+    }
+
+    #[test]
+    fn serialize_empty_storyboard() {
+        let storyboard = Storyboard::default();
+
+        assert!(storyboard.is_empty());
+        assert_eq!(
+            storyboard.to_string(),
+            "//Storyboard Layer 0 (Background)
+//Storyboard Layer 1 (Fail)
+//Storyboard Layer 2 (Pass)
+//Storyboard Layer 3 (Foreground)
+//Storyboard Layer 4 (Overlay)
+//Storyboard Sound Samples
+"
+        );
+    }
+}
@@ -1,23 +1,25 @@
 use crate::error::BeatmapParseError;
 use crate::error::BeatmapParseError::InvalidFormat;
-use crate::section::CommaListElement;
+use crate::section::{CommaListElement, Timed};
 use crate::types::timing_points::*;
 use crate::types::{OsuBool, SampleSet};
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
+use std::time::Duration;
 
 //////////////////////////////////////////////////////////////////////////////////////////////////
 
 /// Representation of a timing point.
 /// Each timing point influences a specified portion of the map, commonly called a "timing section"
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Hash)]
 pub struct TimingPoint {
     /// Start time of the timing section, in milliseconds from the beginning of the beatmap's audio.
     /// The end of the timing section is the next timing point's time (or never, if this is the last timing point).
-    pub time: u32,
+    pub time: i32,
     /// This property has two meanings:
     /// - For uninherited timing points, the duration of a beat, in milliseconds.
     /// - For inherited timing points, a negative inverse slider velocity multiplier, as a percentage.
-    pub beat_length: f32,
+    pub beat_length: BeatLength,
     /// Amount of beats in a measure. Inherited timing points ignore this property.
     pub meter: u32,
     /// Default sample set for hit objects
@@ -33,6 +35,50 @@ pub struct TimingPoint {
     pub effects: Effects,
 }
 
+impl TimingPoint {
+    /// Start time of the timing section, as a [Duration] from the beginning of the beatmap's audio.
+    pub fn time_duration(&self) -> Duration {
+        // Durations can't be negative, times before the audio start are clamped to it
+        Duration::from_millis(self.time.max(0) as u64)
+    }
+
+    /// Set the start time of the timing section from a [Duration], truncated to the millisecond.
+    pub fn set_time_from_duration(&mut self, duration: Duration) {
+        self.time = i32::try_from(duration.as_millis()).unwrap_or(i32::MAX);
+    }
+
+    /// Slider velocity multiplier given by the timing point.
+    /// Uninherited timing points reset it to `1`.
+    pub fn slider_velocity(&self) -> f32 {
+        match bool::from(self.is_uninherited) {
+            true => 1.,
+            false => self.beat_length.as_sv_multiplier().unwrap_or(1.),
+        }
+    }
+
+    /// Whether kiai time is active from this timing point.
+    pub fn is_kiai(&self) -> bool {
+        self.effects.contains(Effects::KIAI)
+    }
+
+    /// Enable or disable kiai time from this timing point, keeping the other effects.
+    pub fn set_kiai(&mut self, on: bool) {
+        self.effects.set(Effects::KIAI, on);
+    }
+
+    /// Omit or not the first barline of this timing point in osu!taiko and osu!mania,
+    /// keeping the other effects.
+    pub fn set_omit_barline(&mut self, on: bool) {
+        self.effects.set(Effects::OMIT_BARLINE, on);
+    }
+}
+
+impl Timed for TimingPoint {
+    fn time(&self) -> i32 {
+        self.time
+    }
+}
+
 impl FromStr for TimingPoint {
     type Err = BeatmapParseError;
 
@@ -40,10 +86,10 @@ impl FromStr for TimingPoint {
         let s: Vec<&str> = s.trim().split(",").map(|x| x.trim()).collect();
 
         Ok(TimingPoint {
-            time: u32::from_str(s[0]).map_err(|_| InvalidFormat {
+            time: i32::from_str(s[0]).map_err(|_| InvalidFormat {
                 field: "time".to_string(),
             })?,
-            beat_length: f32::from_str(s[1]).map_err(|_| InvalidFormat {
+            beat_length: BeatLength::from_str(s[1]).map_err(|_| InvalidFormat {
                 field: "beat_length".to_string(),
             })?,
             meter: u32::from_str(s[2]).map_err(|_| InvalidFormat {
@@ -66,18 +112,19 @@ impl FromStr for TimingPoint {
     }
 }
 
-impl ToString for TimingPoint {
-    fn to_string(&self) -> String {
-        format!(
+impl Display for TimingPoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
             "{},{},{},{},{},{},{},{}",
-            self.time.to_string(),
-            self.beat_length.to_string(),
-            self.meter.to_string(),
-            self.sample_set.to_string(),
-            self.sample_index.to_string(),
-            self.volume.to_string(),
-            self.is_uninherited.to_string(),
-            self.effects.bits().to_string()
+            self.time,
+            self.beat_length,
+            self.meter,
+            self.sample_set,
+            self.sample_index,
+            self.volume,
+            self.is_uninherited,
+            self.effects.bits()
         )
     }
 }
@@ -119,12 +166,22 @@ mod tests {
         assert_eq!(timing_points[1].effects, Effects::KIAI);
     }
 
+    #[test]
+    fn parse_timing_points_with_empty_lines() {
+        let timing_points: CommaListOf<TimingPoint> =
+            CommaListOf::parse("10000,333.33,4,0,0,100,1,1\n\n  \n12000,-25,4,3,0,100,0,1")
+                .unwrap();
+
+        assert_eq!(timing_points.len(), 2);
+        assert!(CommaListOf::<TimingPoint>::parse("").unwrap().is_empty());
+    }
+
     #[test]
     fn serialize_timing_points() {
         let mut timing_points: CommaListOf<TimingPoint> = CommaListOf::new();
         timing_points.push(TimingPoint {
             time: 10000,
-            beat_length: 333.33,
+            beat_length: 333.33.into(),
             meter: 4,
             sample_set: SampleSet::Default,
             sample_index: 0,
@@ -134,7 +191,7 @@ mod tests {
         });
         timing_points.push(TimingPoint {
             time: 12000,
-            beat_length: -25.0,
+            beat_length: (-25.0).into(),
             meter: 4,
             sample_set: SampleSet::Drum,
             sample_index: 0,
@@ -146,9 +203,28 @@ mod tests {
         assert_eq!(timing_points.serialize(), TEST_SECTION)
     }
 
+    #[test]
+    fn insert_sorted_timing_points() {
+        let mut timing_points: CommaListOf<TimingPoint> = CommaListOf::new();
+
+        for line in ["12000,-25,4,3,0,100,0,1", "10000,333.33,4,0,0,100,1,1"] {
+            timing_points.insert_sorted(line.parse().unwrap());
+        }
+
+        assert_eq!(timing_points.serialize(), TEST_SECTION);
+
+        timing_points.insert_sorted("11000,-50,4,3,0,100,0,0".parse().unwrap());
+
+        assert_eq!(
+            timing_points.iter().map(|x| x.time).collect::<Vec<_>>(),
+            vec![10000, 11000, 12000]
+        );
+    }
+
     mod timing_point {
         use super::*;
         use crate::section::CommaListElement;
+        use std::time::Duration;
 
         const TEST_TIMING_POINT: &'static str = "10000,333.33,4,0,0,100,1,1";
 
@@ -170,7 +246,7 @@ mod tests {
         fn serialize_timing_point() {
             let timing_point = TimingPoint {
                 time: 10000,
-                beat_length: 333.33,
+                beat_length: 333.33.into(),
                 meter: 4,
                 sample_set: SampleSet::Default,
                 sample_index: 0,
@@ -181,5 +257,58 @@ mod tests {
 
             assert_eq!(timing_point.serialize(), TEST_TIMING_POINT)
         }
+
+        #[test]
+        fn toggle_effects() {
+            let mut timing_point = TimingPoint::parse("10000,333.33,4,0,0,100,1,0").unwrap();
+
+            timing_point.set_kiai(true);
+            assert!(timing_point.is_kiai());
+            assert_eq!(timing_point.serialize(), "10000,333.33,4,0,0,100,1,1");
+
+            timing_point.set_omit_barline(true);
+            assert_eq!(timing_point.serialize(), "10000,333.33,4,0,0,100,1,9");
+            assert_eq!(
+                TimingPoint::parse("10000,333.33,4,0,0,100,1,9").unwrap(),
+                timing_point
+            );
+
+            timing_point.set_kiai(false);
+            assert!(!timing_point.is_kiai());
+            assert_eq!(timing_point.serialize(), "10000,333.33,4,0,0,100,1,8");
+
+            timing_point.set_omit_barline(false);
+            assert_eq!(timing_point.effects, Effects::empty());
+        }
+
+        #[test]
+        fn parse_negative_time_timing_point() {
+            let timing_point = TimingPoint::parse("-28,333.33,4,0,0,100,1,0").unwrap();
+
+            assert_eq!(timing_point.time, -28);
+            assert_eq!(timing_point.time_duration(), Duration::ZERO);
+            assert_eq!(timing_point.serialize(), "-28,333.33,4,0,0,100,1,0");
+        }
+
+        #[test]
+        fn parse_named_sample_set_timing_point() {
+            let timing_point = TimingPoint::parse("10000,333.33,4,Normal,0,100,1,1").unwrap();
+
+            assert_eq!(timing_point.sample_set, SampleSet::Normal);
+            assert_eq!(timing_point.serialize(), "10000,333.33,4,1,0,100,1,1");
+            assert!(TimingPoint::parse("10000,333.33,4,Loud,0,100,1,1").is_err());
+        }
+
+        #[test]
+        fn time_duration() {
+            let mut timing_point = TimingPoint::parse(TEST_TIMING_POINT).unwrap();
+
+            assert_eq!(timing_point.time_duration(), Duration::from_millis(10000));
+
+            timing_point.set_time_from_duration(Duration::from_millis(1121));
+
+            assert_eq!(timing_point.time, 1121);
+            assert_eq!(timing_point.time_duration(), Duration::from_millis(1121));
+        }
     }
 }
@@ -0,0 +1,86 @@
+use crate::section::hit_objects::HitObjectType;
+use crate::BeatmapLevel;
+
+/// Overview of a beatmap, computed once with [BeatmapLevel::summary].
+/// It doesn't borrow the beatmap so it can be cached by the caller.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BeatmapSummary {
+    /// Time of the first hit object, if any.
+    pub first_object_time: Option<i32>,
+    /// Time when the last hit object ends, if any.
+    pub last_object_time: Option<i32>,
+    /// Duration between the start of the first hit object and the end of the last one,
+    /// in milliseconds.
+    pub length: u32,
+    pub hit_circles: usize,
+    pub sliders: usize,
+    pub spinners: usize,
+    pub mania_holds: usize,
+    /// Lowest and highest BPM of the uninherited timing points, if any.
+    pub bpm_range: Option<(f64, f64)>,
+}
+
+impl BeatmapSummary {
+    /// Total amount of hit objects.
+    pub fn object_count(&self) -> usize {
+        self.hit_circles + self.sliders + self.spinners + self.mania_holds
+    }
+}
+
+impl BeatmapLevel {
+    /// Compute a [BeatmapSummary] of the beatmap.
+    pub fn summary(&self) -> BeatmapSummary {
+        let mut summary = BeatmapSummary {
+            first_object_time: self.first_object_time(),
+            last_object_time: self.last_object_time(),
+            ..Default::default()
+        };
+
+        if let (Some(first), Some(last)) = (summary.first_object_time, summary.last_object_time) {
+            summary.length = u32::try_from(last - first).unwrap_or(0);
+        }
+
+        self.hit_objects.iter().for_each(|x| match x.object_params {
+            HitObjectType::HitCircle => summary.hit_circles += 1,
+            HitObjectType::Slider(_) => summary.sliders += 1,
+            HitObjectType::Spinner(_) => summary.spinners += 1,
+            HitObjectType::ManiaHold(_) => summary.mania_holds += 1,
+        });
+
+        summary.bpm_range = self.bpm_range();
+
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::summary::BeatmapSummary;
+    use crate::BeatmapLevel;
+    use std::path::Path;
+
+    const TEST_BEATMAP_LEVEL_PATH: &str = "./assets/examples/test.osu";
+
+    #[test]
+    fn summary() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let summary = beatmap_level.summary();
+        let (min_bpm, max_bpm) = summary.bpm_range.unwrap();
+
+        assert_eq!(summary.first_object_time, Some(1121));
+        assert_eq!(summary.last_object_time, Some(159335));
+        assert_eq!(summary.length, 158214);
+        assert_eq!(summary.hit_circles, 234);
+        assert_eq!(summary.sliders, 233);
+        assert_eq!(summary.spinners, 1);
+        assert_eq!(summary.mania_holds, 0);
+        assert_eq!(summary.object_count(), beatmap_level.hit_objects.len());
+        assert!((min_bpm - 168.).abs() < 0.001);
+        assert!((max_bpm - 168.).abs() < 0.001);
+    }
+
+    #[test]
+    fn summary_empty_beatmap() {
+        assert_eq!(BeatmapLevel::new().summary(), BeatmapSummary::default());
+    }
+}
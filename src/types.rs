@@ -1,20 +1,56 @@
 use crate::error::MapTypeError::UnexpectedBoolValue;
 use crate::error::{BeatmapParseError, MapTypeError};
 use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct OsuBool(bool);
+/// Boolean as written in a beatmap, either numeric (`0`/`1`) or textual (`false`/`true`).
+/// The form read from the source is kept so that serializing writes it back unchanged;
+/// it's ignored when comparing or hashing two values.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OsuBool {
+    value: bool,
+    textual: bool,
+}
+
+impl OsuBool {
+    /// Whether the value is written as `true`/`false` rather than `1`/`0`.
+    pub fn is_textual(&self) -> bool {
+        self.textual
+    }
+
+    /// Set whether the value is written as `true`/`false` rather than `1`/`0`.
+    pub fn set_textual(&mut self, textual: bool) {
+        self.textual = textual
+    }
+}
 
 impl From<bool> for OsuBool {
     fn from(boolean: bool) -> Self {
-        Self { 0: boolean }
+        Self {
+            value: boolean,
+            textual: false,
+        }
     }
 }
 
 impl From<OsuBool> for bool {
     fn from(osu_boolean: OsuBool) -> Self {
-        osu_boolean.0
+        osu_boolean.value
+    }
+}
+
+impl PartialEq for OsuBool {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for OsuBool {}
+
+impl Hash for OsuBool {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state)
     }
 }
 
@@ -22,29 +58,30 @@ impl FromStr for OsuBool {
     type Err = MapTypeError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "true" | "1" => Ok(Self { 0: true }),
-            "false" | "0" => Ok(Self { 0: false }),
-            _ => Err(UnexpectedBoolValue),
-        }
-    }
-}
+        let (value, textual) = match s {
+            "true" => (true, true),
+            "false" => (false, true),
+            "1" => (true, false),
+            "0" => (false, false),
+            _ => return Err(UnexpectedBoolValue),
+        };
 
-impl Default for OsuBool {
-    fn default() -> Self {
-        Self { 0: false }
+        Ok(Self { value, textual })
     }
 }
 
 impl Display for OsuBool {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", i8::from(self.0))
+        match self.textual {
+            true => write!(f, "{}", self.value),
+            false => write!(f, "{}", i8::from(self.value)),
+        }
     }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub enum SampleSet {
     #[default]
     Default,
@@ -70,19 +107,33 @@ impl TryFrom<u8> for SampleSet {
 impl FromStr for SampleSet {
     type Err = BeatmapParseError;
 
+    /// Parse a sample set from its integer form, or from its name as written by some
+    /// third-party exports (`Normal`, `Soft`, `Drum`).
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let to_u8 = u8::from_str(s).unwrap();
-        Ok(to_u8.try_into().unwrap())
+        let invalid_format = || BeatmapParseError::InvalidFormat {
+            field: "sample_set".to_string(),
+        };
+
+        match s {
+            "Default" => Ok(SampleSet::Default),
+            "Normal" => Ok(SampleSet::Normal),
+            "Soft" => Ok(SampleSet::Soft),
+            "Drum" => Ok(SampleSet::Drum),
+            _ => u8::from_str(s)
+                .map_err(|_| invalid_format())?
+                .try_into()
+                .map_err(|_| invalid_format()),
+        }
     }
 }
 
-impl ToString for SampleSet {
-    fn to_string(&self) -> String {
+impl Display for SampleSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            SampleSet::Default => String::from("0"),
-            SampleSet::Normal => String::from("1"),
-            SampleSet::Soft => String::from("2"),
-            SampleSet::Drum => String::from("3"),
+            SampleSet::Default => write!(f, "0"),
+            SampleSet::Normal => write!(f, "1"),
+            SampleSet::Soft => write!(f, "2"),
+            SampleSet::Drum => write!(f, "3"),
         }
     }
 }
@@ -98,7 +149,7 @@ pub mod general {
     use std::fmt::{Display, Formatter};
     use std::str::FromStr;
 
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum Gamemode {
         STD,
         TAIKO,
@@ -203,7 +254,7 @@ pub mod general {
     }
 
     ///////////////////////////////////////////////////////////////////////////////////////////////
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum SampleSet {
         NORMAL,
         SOFT,
@@ -247,6 +298,28 @@ pub mod general {
         }
     }
 
+    /// The sample set of hit objects and timing points has no `Default` variant here: it is
+    /// converted to [SampleSet::NORMAL], the default sample set of the beatmap.
+    impl From<super::SampleSet> for SampleSet {
+        fn from(sample_set: super::SampleSet) -> Self {
+            match sample_set {
+                super::SampleSet::Default | super::SampleSet::Normal => SampleSet::NORMAL,
+                super::SampleSet::Soft => SampleSet::SOFT,
+                super::SampleSet::Drum => SampleSet::DRUM,
+            }
+        }
+    }
+
+    impl From<SampleSet> for super::SampleSet {
+        fn from(sample_set: SampleSet) -> Self {
+            match sample_set {
+                SampleSet::NORMAL => super::SampleSet::Normal,
+                SampleSet::SOFT => super::SampleSet::Soft,
+                SampleSet::DRUM => super::SampleSet::Drum,
+            }
+        }
+    }
+
     ///////////////////////////////////////////////////////////////////////////////////////////////
     #[derive(Debug, PartialEq, Eq)]
     pub enum Countdown {
@@ -302,12 +375,137 @@ pub mod general {
             write!(f, "{}", i32::from(self))
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::types::general::SampleSet;
+
+        #[test]
+        fn sample_set_conversions() {
+            for sample_set in [SampleSet::NORMAL, SampleSet::SOFT, SampleSet::DRUM] {
+                let numeric = crate::types::SampleSet::from(sample_set);
+                assert_eq!(SampleSet::from(numeric), sample_set);
+            }
+
+            assert_eq!(
+                crate::types::SampleSet::from(SampleSet::SOFT),
+                crate::types::SampleSet::Soft
+            );
+            assert_eq!(
+                SampleSet::from(crate::types::SampleSet::Default),
+                SampleSet::NORMAL
+            );
+        }
+    }
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 
 pub mod timing_points {
     use bitflags::bitflags;
+    use std::fmt::{Display, Formatter};
+    use std::hash::{Hash, Hasher};
+    use std::num::ParseFloatError;
+    use std::ops::{Deref, DerefMut};
+    use std::str::FromStr;
+
+    /// Beat length of a timing point, as written in the beatmap file.
+    /// - For uninherited timing points, the duration of a beat, in milliseconds.
+    /// - For inherited timing points, a negative inverse slider velocity multiplier, as a percentage.
+    ///
+    /// The value is kept as a `f64` so beat lengths such as `357.142857142857` are written back
+    /// as they were read. Conversions from and comparisons with `f32` are still provided.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+    pub struct BeatLength(pub f64);
+
+    impl BeatLength {
+        /// Whether or not the value is the one of an inherited timing point (negative).
+        pub fn is_inherited(&self) -> bool {
+            self.0 < 0.
+        }
+
+        /// Beats per minute given by the beat length of an uninherited timing point.
+        pub fn as_bpm(&self) -> Option<f64> {
+            match self.0 > 0. {
+                true => Some(60000. / self.0),
+                false => None,
+            }
+        }
+
+        /// Slider velocity multiplier given by the beat length of an inherited timing point.
+        pub fn as_sv_multiplier(&self) -> Option<f32> {
+            match self.is_inherited() {
+                true => Some((-100. / self.0) as f32),
+                false => None,
+            }
+        }
+    }
+
+    impl Hash for BeatLength {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.0.to_bits().hash(state)
+        }
+    }
+
+    impl Deref for BeatLength {
+        type Target = f64;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl DerefMut for BeatLength {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.0
+        }
+    }
+
+    impl From<f64> for BeatLength {
+        fn from(beat_length: f64) -> Self {
+            Self(beat_length)
+        }
+    }
+
+    impl From<f32> for BeatLength {
+        fn from(beat_length: f32) -> Self {
+            Self(f64::from(beat_length))
+        }
+    }
+
+    impl From<BeatLength> for f64 {
+        fn from(beat_length: BeatLength) -> Self {
+            beat_length.0
+        }
+    }
+
+    impl PartialEq<f64> for BeatLength {
+        fn eq(&self, other: &f64) -> bool {
+            self.0 == *other
+        }
+    }
+
+    impl PartialEq<f32> for BeatLength {
+        fn eq(&self, other: &f32) -> bool {
+            self.0 == f64::from(*other)
+        }
+    }
+
+    impl FromStr for BeatLength {
+        type Err = ParseFloatError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Ok(Self(f64::from_str(s)?))
+        }
+    }
+
+    impl Display for BeatLength {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    ///////////////////////////////////////////////////////////////////////////////////////////////
 
     bitflags! {
         /// Timing points have two extra effects that can be toggled using bits 0 and 3
@@ -316,7 +514,7 @@ pub mod timing_points {
             /// Whether or not [kiai time](https://osu.ppy.sh/wiki/en/Gameplay/Kiai_time) is enabled
             const KIAI = 0b00000001;
             /// Whether or not the first barline is omitted in osu!taiko and osu!mania
-            const OMIT_BARLINE = 0b00000100;
+            const OMIT_BARLINE = 0b00001000;
         }
     }
 
@@ -325,4 +523,59 @@ pub mod timing_points {
             Self { bits: 0 }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::types::timing_points::BeatLength;
+
+        #[test]
+        fn uninherited_beat_length() {
+            let beat_length = BeatLength::from(500.);
+
+            assert!(!beat_length.is_inherited());
+            assert_eq!(beat_length.as_bpm(), Some(120.));
+            assert_eq!(beat_length.as_sv_multiplier(), None);
+            assert_eq!(beat_length.to_string(), "500");
+        }
+
+        #[test]
+        fn inherited_beat_length() {
+            let beat_length: BeatLength = "-50".parse().unwrap();
+
+            assert!(beat_length.is_inherited());
+            assert_eq!(beat_length.as_bpm(), None);
+            assert_eq!(beat_length.as_sv_multiplier(), Some(2.));
+            assert_eq!(beat_length, -50.);
+            assert_eq!(beat_length.to_string(), "-50");
+        }
+
+        #[test]
+        fn beat_length_precision() {
+            let beat_length: BeatLength = "357.142857142857".parse().unwrap();
+
+            assert_eq!(beat_length.to_string(), "357.142857142857");
+        }
+
+        #[test]
+        fn beat_length_from_f32() {
+            let beat_length = BeatLength::from(-50f32);
+
+            assert_eq!(beat_length, -50f32);
+            assert_eq!(beat_length, -50f64);
+            assert_eq!(beat_length.as_sv_multiplier(), Some(2.));
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub mod difficulty {
+    /// Mods altering the difficulty settings of a beatmap.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum DiffMod {
+        /// Multiply CS by 1.3 and AR, OD, HP by 1.4 (capped at 10), and flip the playfield vertically.
+        HardRock,
+        /// Halve CS, AR, OD and HP.
+        Easy,
+    }
 }
@@ -0,0 +1,164 @@
+use crate::section::hit_objects::HitObjectType;
+use crate::types::general::Gamemode;
+use crate::BeatmapLevel;
+
+/// Issue found in a beatmap which doesn't prevent it from being parsed,
+/// but may prevent it from being played correctly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationWarning {
+    /// The beatmap doesn't reference any audio file.
+    EmptyAudioFilename,
+    /// A difficulty setting is outside of its allowed range.
+    DifficultyOutOfRange { field: &'static str, value: f32 },
+    /// A hit object, at the given index in the hit objects, has a type which doesn't exist in
+    /// the gamemode of the beatmap.
+    ObjectNotAllowedInMode {
+        index: usize,
+        object_type: &'static str,
+    },
+    /// A spinner or a hold, at the given index in the hit objects, ends before it starts.
+    NegativeDurationObject { index: usize },
+    /// A hit object, at the given index in the hit objects, is placed too far outside of the
+    /// playfield to be seen.
+    /// See [HitObject::in_playfield](crate::section::hit_objects::HitObject::in_playfield).
+    ObjectOutOfPlayfield { index: usize },
+}
+
+impl BeatmapLevel {
+    /// Check the beatmap for issues which were tolerated while parsing it.
+    pub fn validate(&self) -> Vec<ValidationWarning> {
+        let mut warnings = Vec::new();
+
+        if !self.general.has_audio() {
+            warnings.push(ValidationWarning::EmptyAudioFilename);
+        }
+
+        warnings.append(&mut self.difficulty.validate(&self.general.mode));
+        warnings.append(&mut self.validate_mode());
+        warnings.extend(
+            self.hit_objects
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| x.has_negative_duration())
+                .map(|(index, _)| ValidationWarning::NegativeDurationObject { index }),
+        );
+        warnings.extend(
+            self.hit_objects
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| !x.in_playfield())
+                .map(|(index, _)| ValidationWarning::ObjectOutOfPlayfield { index }),
+        );
+
+        warnings
+    }
+
+    /// Check that every hit object is of a type playable in the gamemode of the beatmap.
+    /// osu!mania only has circles and holds, while holds only exist in osu!mania.
+    pub fn validate_mode(&self) -> Vec<ValidationWarning> {
+        let is_mania = self.general.mode == Gamemode::MANIA;
+
+        self.hit_objects
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| match x.object_params {
+                HitObjectType::HitCircle => false,
+                HitObjectType::Slider(_) | HitObjectType::Spinner(_) => is_mania,
+                HitObjectType::ManiaHold(_) => !is_mania,
+            })
+            .map(|(index, x)| ValidationWarning::ObjectNotAllowedInMode {
+                index,
+                object_type: x.object_params.name(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::section::hit_objects::{HitObject, HitObjectType, ManiaHoldParams};
+    use crate::types::general::Gamemode;
+    use crate::validation::ValidationWarning;
+    use crate::BeatmapLevel;
+    use std::path::Path;
+
+    const TEST_BEATMAP_LEVEL_PATH: &str = "./assets/examples/test.osu";
+
+    #[test]
+    fn validate_beatmap() {
+        let beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+
+        assert!(beatmap_level.validate().is_empty());
+    }
+
+    #[test]
+    fn validate_empty_audio_filename() {
+        let mut beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        beatmap_level.general = "AudioLeadIn: 0\nPreviewTime: 2000".parse().unwrap();
+
+        assert_eq!(
+            beatmap_level.validate(),
+            vec![ValidationWarning::EmptyAudioFilename]
+        );
+    }
+
+    #[test]
+    fn validate_negative_duration() {
+        let mut beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let index = beatmap_level
+            .hit_objects
+            .iter()
+            .position(|x| matches!(x.object_params, HitObjectType::Spinner(_)))
+            .unwrap();
+        let spinner = &mut beatmap_level.hit_objects[index];
+        if let HitObjectType::Spinner(params) = &mut spinner.object_params {
+            params.end_time = spinner.time - 1000;
+        }
+
+        assert_eq!(
+            beatmap_level.validate(),
+            vec![ValidationWarning::NegativeDurationObject { index }]
+        );
+    }
+
+    #[test]
+    fn validate_out_of_playfield() {
+        let mut beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        beatmap_level.hit_objects[2].x = 1000;
+
+        assert_eq!(
+            beatmap_level.validate(),
+            vec![ValidationWarning::ObjectOutOfPlayfield { index: 2 }]
+        );
+    }
+
+    #[test]
+    fn validate_mode() {
+        let mut beatmap_level = BeatmapLevel::open(Path::new(TEST_BEATMAP_LEVEL_PATH)).unwrap();
+        let index = beatmap_level.hit_objects.len();
+        beatmap_level.hit_objects.push(HitObject {
+            time: 200000,
+            object_params: HitObjectType::ManiaHold(ManiaHoldParams { end_time: 201000 }),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            beatmap_level.validate_mode(),
+            vec![ValidationWarning::ObjectNotAllowedInMode {
+                index,
+                object_type: "ManiaHold",
+            }]
+        );
+
+        beatmap_level.general.mode = Gamemode::MANIA;
+
+        assert!(beatmap_level.validate_mode().iter().all(|x| !matches!(
+            x,
+            ValidationWarning::ObjectNotAllowedInMode {
+                object_type: "ManiaHold",
+                ..
+            }
+        )));
+        assert!(!beatmap_level.validate_mode().is_empty());
+    }
+}
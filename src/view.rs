@@ -0,0 +1,197 @@
+use crate::section::hit_objects::HitObjectTypeFlag;
+use crate::section::metadata::split_tags;
+use crate::section::split_sections;
+use crate::BeatmapLevel;
+use std::str::FromStr;
+
+/// Read-only view of a beatmap borrowing from its source text.
+///
+/// Only the section boundaries are located when the view is created, fields are looked up when
+/// requested and returned as slices of the source, so no owned structure is allocated.
+/// Use [BeatmapLevel] when the beatmap needs to be modified or fully validated.
+#[derive(Debug, Clone)]
+pub struct BeatmapView<'a> {
+    sections: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> BeatmapView<'a> {
+    pub fn new(s: &'a str) -> Self {
+        BeatmapView {
+            sections: split_sections(s).collect(),
+        }
+    }
+
+    /// Raw content of a section, if present. A repeated section overrides the previous one.
+    pub fn section(&self, name: &str) -> Option<&'a str> {
+        self.sections
+            .iter()
+            .rev()
+            .find(|(x, _)| *x == name)
+            .map(|(_, content)| *content)
+    }
+
+    /// Value of a `key: value` field of a section, if present.
+    pub fn value(&self, section: &str, key: &str) -> Option<&'a str> {
+        // osu! keeps the last occurrence of a key defined multiple times
+        self.section(section)?
+            .lines()
+            .rev()
+            .filter_map(|x| x.split_once(':'))
+            .find(|(x, _)| x.trim() == key)
+            .map(|(_, value)| value.trim())
+    }
+
+    pub fn audio_filename(&self) -> Option<&'a str> {
+        self.value("General", "AudioFilename")
+    }
+
+    pub fn title(&self) -> Option<&'a str> {
+        self.value("Metadata", "Title")
+    }
+
+    pub fn artist(&self) -> Option<&'a str> {
+        self.value("Metadata", "Artist")
+    }
+
+    pub fn creator(&self) -> Option<&'a str> {
+        self.value("Metadata", "Creator")
+    }
+
+    pub fn version(&self) -> Option<&'a str> {
+        self.value("Metadata", "Version")
+    }
+
+    /// Search terms of the beatmap, split as [MetadataSection](crate::section::metadata::MetadataSection)
+    /// does: on spaces, keeping a quoted tag as a single tag without its quotes.
+    pub fn tags(&self) -> impl Iterator<Item = &'a str> {
+        split_tags(self.value("Metadata", "Tags").unwrap_or_default())
+    }
+
+    /// Iterate over the hit objects of the beatmap, parsing only their common fields.
+    /// Lines which can't be parsed are skipped.
+    pub fn hit_objects(&self) -> impl Iterator<Item = HitObjectView<'a>> {
+        self.section("HitObjects")
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|x| HitObjectView::from_line(x.trim()))
+    }
+}
+
+impl BeatmapLevel {
+    /// Create a read-only [BeatmapView] borrowing from the given beatmap text.
+    pub fn view(s: &str) -> BeatmapView<'_> {
+        BeatmapView::new(s)
+    }
+}
+
+/// Borrowed hit object giving access to the fields common to every object type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HitObjectView<'a> {
+    pub x: i32,
+    pub y: i32,
+    pub time: i32,
+    type_flags: HitObjectTypeFlag,
+    /// Remaining fields of the object, starting with the hit sound, as written in the source.
+    pub params: &'a str,
+}
+
+impl<'a> HitObjectView<'a> {
+    fn from_line(line: &'a str) -> Option<Self> {
+        let mut fields = line.splitn(5, ',');
+
+        Some(HitObjectView {
+            x: i32::from_str(fields.next()?.trim()).ok()?,
+            y: i32::from_str(fields.next()?.trim()).ok()?,
+            time: i32::from_str(fields.next()?.trim()).ok()?,
+            type_flags: HitObjectTypeFlag::from_bits_truncate(
+                u8::from_str(fields.next()?.trim()).ok()?,
+            ),
+            params: fields.next().unwrap_or_default(),
+        })
+    }
+
+    pub fn is_hit_circle(&self) -> bool {
+        self.type_flags.contains(HitObjectTypeFlag::HIT_CIRCLE)
+    }
+
+    pub fn is_slider(&self) -> bool {
+        self.type_flags.contains(HitObjectTypeFlag::SLIDER)
+    }
+
+    pub fn is_spinner(&self) -> bool {
+        self.type_flags.contains(HitObjectTypeFlag::SPINNER)
+    }
+
+    pub fn is_mania_hold(&self) -> bool {
+        self.type_flags.contains(HitObjectTypeFlag::MANIA_HOLD)
+    }
+
+    pub fn new_combo(&self) -> bool {
+        self.type_flags.contains(HitObjectTypeFlag::NEW_COMBO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::section::hit_objects::HitObjectType;
+    use crate::section::metadata::MetadataSection;
+    use crate::BeatmapLevel;
+    use std::fs;
+    use std::str::FromStr;
+
+    const TEST_BEATMAP_LEVEL_PATH: &str = "./assets/examples/test.osu";
+
+    #[test]
+    fn view_matches_owned_parse() {
+        let s = fs::read_to_string(TEST_BEATMAP_LEVEL_PATH).unwrap();
+        let beatmap_level = BeatmapLevel::parse(&s).unwrap();
+        let view = BeatmapLevel::view(&s);
+
+        assert_eq!(
+            view.audio_filename(),
+            Some(beatmap_level.general.audio_filename.as_str())
+        );
+        assert_eq!(view.title(), Some(beatmap_level.metadata.title.as_str()));
+        assert_eq!(
+            view.version(),
+            Some(beatmap_level.metadata.version.as_str())
+        );
+        assert!(view
+            .tags()
+            .eq(beatmap_level.metadata.tags.iter().map(|x| x.as_str())));
+        assert_eq!(view.hit_objects().count(), beatmap_level.hit_objects.len());
+
+        for (view, owned) in view.hit_objects().zip(beatmap_level.hit_objects.iter()) {
+            assert_eq!((view.x, view.y, view.time), (owned.x, owned.y, owned.time));
+            assert_eq!(
+                view.is_slider(),
+                matches!(owned.object_params, HitObjectType::Slider(_))
+            );
+            assert_eq!(view.new_combo(), owned.new_combo);
+        }
+    }
+
+    #[test]
+    fn view_missing_fields() {
+        let view = BeatmapLevel::view("osu file format v14\n\n[Metadata]\nTitle: a\nTitle: b\n");
+
+        assert_eq!(view.title(), Some("b"));
+        assert_eq!(view.artist(), None);
+        assert_eq!(view.audio_filename(), None);
+        assert_eq!(view.hit_objects().count(), 0);
+    }
+
+    #[test]
+    fn view_quoted_tags() {
+        let tags = "Tags:dnb  \"drum and bass\" liquid \"\"";
+        let metadata = MetadataSection::from_str(tags).unwrap();
+        let s = format!("osu file format v14\n\n[Metadata]\n{}\n", tags);
+        let view = BeatmapLevel::view(&s);
+
+        assert_eq!(
+            view.tags().collect::<Vec<_>>(),
+            vec!["dnb", "drum and bass", "liquid"]
+        );
+        assert!(view.tags().eq(metadata.tags.iter().map(|x| x.as_str())));
+    }
+}